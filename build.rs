@@ -40,6 +40,7 @@ fn bundle_tpf() -> io::Result<()> {
 
 fn compile_shaders() -> io::Result<()> {
     println!("cargo::rerun-if-changed=shaders/ToneMap_PostHook.hlsl");
+    println!("cargo::rerun-if-changed=shaders/ErfpsParams.hlsli");
 
     let out = format!("{}/ToneMap_PostHook.ppo", env::var("OUT_DIR").unwrap());
     let dxc = env::var("DXC_PATH").unwrap_or_else(|_| "dxc".to_owned());
@@ -0,0 +1,56 @@
+// The parameter block shared with `ShaderParams` in "src/shaders/params.rs".
+// Bump the version on both sides whenever the layout changes.
+#define ERFPS_PARAMS_VERSION 3
+
+struct ErfpsParams
+{
+    uint version;
+    uint flags;
+    // Barrel cylindricity and FOV correction strength scaled by the screen width.
+    float2 correctParam;
+    float2 crosshairScaleReciprocal;
+    float2 crosshairOffset;
+    uint crosshairColor;
+    uint crosshairOutlineColor;
+    float crosshairOutlineWidth;
+    float crosshairOpacity;
+    float vignetteStrength;
+    float hitMarkerProgress;
+    uint hitMarkerColor;
+    // The tangent of half the horizontal FOV.
+    float fovTangent;
+    // Panini projection distance and vertical compensation.
+    float2 paniniParam;
+    // Contrast adaptive sharpening strength where FOV correction magnifies the image.
+    float sharpening;
+    // Scales the game's chromatic aberration offsets.
+    float chromaticAberrationScale;
+    uint4 reserved[4];
+    uint4 crosshairShape[4];
+};
+
+// Bit fields of `flags`, shared with `ShaderFlag` in "src/shaders/params.rs".
+#define ERFPS_FOV_CORRECTION_SHIFT 0
+#define ERFPS_FOV_CORRECTION_WIDTH 1
+#define ERFPS_BARREL_SHIFT 1
+#define ERFPS_BARREL_WIDTH 1
+#define ERFPS_CROSSHAIR_KIND_SHIFT 2
+#define ERFPS_CROSSHAIR_KIND_WIDTH 3
+#define ERFPS_VIGNETTE_SHIFT 5
+#define ERFPS_VIGNETTE_WIDTH 1
+#define ERFPS_CROSSHAIR_BLEND_SHIFT 6
+#define ERFPS_CROSSHAIR_BLEND_WIDTH 2
+#define ERFPS_HIT_MARKER_STYLE_SHIFT 8
+#define ERFPS_HIT_MARKER_STYLE_WIDTH 2
+#define ERFPS_PROJECTION_SHIFT 10
+#define ERFPS_PROJECTION_WIDTH 2
+#define ERFPS_SHARPENING_SHIFT 12
+#define ERFPS_SHARPENING_WIDTH 1
+#define ERFPS_CHROMATIC_ABERRATION_SCALE_SHIFT 13
+#define ERFPS_CHROMATIC_ABERRATION_SCALE_WIDTH 1
+
+// Every feature is off for a parameter block of a different version.
+#define ERFPS_FLAGS ((int)(g_Erfps.version == ERFPS_PARAMS_VERSION ? g_Erfps.flags : 0u))
+
+// Reads a bit field of the flags, e.g. `ERFPS_FLAG(PROJECTION)`.
+#define ERFPS_FLAG(name) ((ERFPS_FLAGS >> ERFPS_##name##_SHIFT) & ((1 << ERFPS_##name##_WIDTH) - 1))
@@ -1,3 +1,5 @@
+#include "ErfpsParams.hlsli"
+
 Texture2D<float4> g_SourceTexture : register(t0);
 
 cbuffer cbPostProcessCommon : register(b4)
@@ -15,21 +17,10 @@ cbuffer cbPostProcessCommon : register(b4)
 cbuffer cbToneMap : register(b1)
 {
     float3 g_ToneMapInvSceneLumScale;
-    int g_ErfpsFlags;
-    float4 g_ReinhardParam;
-    float4 g_ToneMapParam;
-    float4 g_ToneMapSceneLumScale;
-    float4 g_AdaptParam;
-    float4 g_AdaptCenterWeight;
-    float4 g_BrightPassThreshold;
-    float4 g_GlareLuminance;
-    float4 g_BloomBoostColor;
-    float4 g_vBloomFinalColor;
-    float4 g_vBloomScaleParam;
-    float4x3 g_mtxColorMultiplyer;
+    // Replaces g_ReinhardParam through g_mtxColorMultiplyer.
+    ErfpsParams g_Erfps;
     float4 g_vChromaticAberrationRG;
     float2 g_vChromaticAberrationB;
-    float2 g_ErfpsCorrectParam;
     int4 g_bEnableFlags;
     float4 g_vFeedBackBlurParam;
     float4 g_vVignettingParam;
@@ -40,7 +31,6 @@ cbuffer cbToneMap : register(b1)
     int4 g_vMaxSampleCount;
     float4 g_vScenePreExposure;
     float2 g_vCameraParam;
-    float2 g_ErfpsCrosshairScaleReciprocal;
 };
 
 SamplerState SS_ClampLinear : register(s1);
@@ -51,7 +41,7 @@ float2 MapUvFisheye(float2 uv)
     float2 c = uv - 0.5;
 
     float r2 = c.x * c.x;
-    float strength = g_ErfpsCorrectParam.y;
+    float strength = g_Erfps.correctParam.y;
 
     float f = 1.0 + strength * sqrt(r2) * r2;
     float fMax = 1.0 + strength * 0.125;
@@ -59,6 +49,63 @@ float2 MapUvFisheye(float2 uv)
     return c * f / fMax + 0.5;
 }
 
+// The ratio of rectilinear to Panini vertical coordinates,
+// blended towards 1.0 (straight horizontal lines) by the vertical compensation.
+float PaniniVerticalScale(float scaleCosLon)
+{
+    return lerp(1.0 / scaleCosLon, 1.0, g_Erfps.paniniParam.y);
+}
+
+// Panini projection with distance `d` (g_Erfps.paniniParam.x),
+// zoomed in for the rectilinear image to cover the screen corners.
+float2 MapUvPanini(float2 uv)
+{
+    float aspectRatio = g_vCameraParam.x;
+    float tangent = g_Erfps.fovTangent;
+    float d = g_Erfps.paniniParam.x;
+
+    float cosEdge = rsqrt(1.0 + tangent * tangent);
+    float scaleEdge = (d + 1.0) / (d + cosEdge);
+    float extent = scaleEdge * tangent * cosEdge;
+    extent *= min(1.0, tangent / (extent * PaniniVerticalScale(scaleEdge * cosEdge)));
+
+    float2 p = (2.0 * uv - 1.0) * extent * float2(1.0, 1.0 / aspectRatio);
+
+    // Solve x = S * sin(lon), S = (d + 1) / (d + cos(lon)) for cos(lon).
+    float k = p.x * p.x / ((d + 1.0) * (d + 1.0));
+    float disc = k * k * d * d - (k + 1.0) * (k * d * d - 1.0);
+    float cosLon = (-k * d + sqrt(max(disc, 0.0))) / (k + 1.0);
+    float scaleCosLon = (d + 1.0) / (d + cosLon) * cosLon;
+
+    float2 xy = float2(p.x / scaleCosLon, p.y * PaniniVerticalScale(scaleCosLon));
+
+    return xy / (tangent * float2(1.0, 1.0 / aspectRatio)) * 0.5 + 0.5;
+}
+
+// 2 * tan(theta / 2) for a rectilinear radius of tan(theta).
+float StereographicRadius(float r)
+{
+    return 2.0 * r / (1.0 + sqrt(1.0 + r * r));
+}
+
+// Stereographic projection, zoomed in for the rectilinear image to cover the screen corners.
+float2 MapUvStereographic(float2 uv)
+{
+    float aspectRatio = g_vCameraParam.x;
+    float tangent = g_Erfps.fovTangent;
+
+    float diagonal = sqrt(1.0 + 1.0 / (aspectRatio * aspectRatio));
+    float extent = min(StereographicRadius(tangent), StereographicRadius(tangent * diagonal) / diagonal);
+
+    float2 p = (2.0 * uv - 1.0) * extent * float2(1.0, 1.0 / aspectRatio);
+
+    // Inverse of StereographicRadius.
+    float r2 = dot(p, p);
+    float2 xy = p / (1.0 - r2 * 0.25);
+
+    return xy / (tangent * float2(1.0, 1.0 / aspectRatio)) * 0.5 + 0.5;
+}
+
 // Source: https://www.decarpentier.nl/lens-distortion
 //
 // Copyright (c) 2015, Giliam de Carpentier
@@ -80,9 +127,9 @@ float2 MapUvFisheye(float2 uv)
 float2 MapUvBarrel(float2 uv)
 {
     float aspectRatio = g_vCameraParam.x;
-    float cylindricalRatio = g_ErfpsCorrectParam.x;
+    float cylindricalRatio = g_Erfps.correctParam.x;
 
-    float scaledHeight = g_ErfpsCorrectParam.y * g_vCameraParam.y;
+    float scaledHeight = g_Erfps.correctParam.y * g_vCameraParam.y;
     float cylAspectRatio = aspectRatio * cylindricalRatio;
     float aspectDiagSq = aspectRatio * aspectRatio + 1.0;
     float diagSq = scaledHeight * scaledHeight * aspectDiagSq;
@@ -99,33 +146,165 @@ float2 MapUvBarrel(float2 uv)
     return uvp.xy / uvp.z;
 }
 
-float CrosshairAlpha(float2 uv)
+// The active FOV correction mapping.
+float2 MapUv(float2 uv)
+{
+    int projection = ERFPS_FLAG(PROJECTION);
+    if (ERFPS_FLAG(BARREL)) {
+        return MapUvBarrel(uv);
+    } else if (projection == 1) {
+        return MapUvPanini(uv);
+    } else if (projection == 2) {
+        return MapUvStereographic(uv);
+    } else {
+        return MapUvFisheye(uv);
+    }
+}
+
+// The local magnification of the FOV correction mapping at `uv`,
+// from the determinant of its Jacobian estimated with central differences.
+float Magnification(float2 uv)
+{
+    const float h = 1.0 / 512.0;
+
+    float2 du = (MapUv(uv + float2(h, 0.0)) - MapUv(uv - float2(h, 0.0))) / (2.0 * h);
+    float2 dv = (MapUv(uv + float2(0.0, h)) - MapUv(uv - float2(0.0, h))) / (2.0 * h);
+
+    return rsqrt(max(abs(du.x * dv.y - du.y * dv.x), 1e-6));
+}
+
+// Samples each channel at its own chromatic aberration offset coordinates.
+float3 SampleChannels(float2 rCoord, float2 gCoord, float2 bCoord, float2 offset, float2 texEdge)
+{
+    float r = g_SourceTexture.SampleLevel(SS_ClampLinear, min(rCoord + offset, texEdge), 0).r;
+    float g = g_SourceTexture.SampleLevel(SS_ClampLinear, min(gCoord + offset, texEdge), 0).g;
+    float b = g_SourceTexture.SampleLevel(SS_ClampLinear, min(bCoord + offset, texEdge), 0).b;
+    return float3(r, g, b);
+}
+
+// Contrast adaptive sharpening of `rgb`, after AMD FidelityFX CAS.
+// The neighbors of each channel are sampled around its own coordinates, like `rgb`.
+// `amount` ranges from 0.0 (no sharpening) to 1.0.
+float3 Sharpen(float3 rgb, float2 rCoord, float2 gCoord, float2 bCoord, float2 texEdge, float amount)
+{
+    float2 texel = g_texSizeReciprocal;
+
+    float3 n = SampleChannels(rCoord, gCoord, bCoord, float2(0.0, -texel.y), texEdge);
+    float3 s = SampleChannels(rCoord, gCoord, bCoord, float2(0.0, texel.y), texEdge);
+    float3 w = SampleChannels(rCoord, gCoord, bCoord, float2(-texel.x, 0.0), texEdge);
+    float3 e = SampleChannels(rCoord, gCoord, bCoord, float2(texel.x, 0.0), texEdge);
+
+    float3 mn = min(rgb, min(min(n, s), min(w, e)));
+    float3 mx = max(rgb, max(max(n, s), max(w, e)));
+
+    // Less sharpening where the neighborhood is already high in contrast.
+    float3 amp = sqrt(saturate(min(mn, 1.0 - mx) / max(mx, 1e-5)));
+    float3 weight = -0.2 * amount * amp;
+
+    return saturate((rgb + (n + s + w + e) * weight) / (1.0 + 4.0 * weight));
+}
+
+float SdSegment(float2 p, float2 a, float2 b)
+{
+    float2 pa = p - a;
+    float2 ba = b - a;
+    float h = saturate(dot(pa, ba) / dot(ba, ba));
+    return length(pa - ba * h);
+}
+
+// Custom crosshair made of up to 8 primitives (see "src/shaders/crosshair.rs").
+// `p` is relative to the crosshair center, in fractions of the screen height.
+float CustomCrosshairAlpha(float2 p, float grow)
+{
+    // Sizes are in 1/64 of a pixel at 1080p.
+    const float unit = 1.0 / (64.0 * 1080.0);
+
+    float pixelSize = fwidth(p.y);
+    float alpha = 0.0;
+
+    [unroll]
+    for (int i = 0; i < 8; i++) {
+        uint4 words = g_Erfps.crosshairShape[i / 2];
+        uint2 primitive = (i & 1) ? words.zw : words.xy;
+
+        uint kind = primitive.x & 0xf;
+        if (kind == 0) {
+            continue;
+        }
+
+        float thickness = (primitive.x >> 16) * unit;
+        float gap = (primitive.y & 0xffff) * unit;
+        float len = (primitive.y >> 16) * unit;
+
+        float d = 1.0e9;
+        float halfWidth = thickness * 0.5;
+        switch (kind) {
+            default:
+                break;
+            case 1: {
+                float2 q = abs(p);
+                d = min(SdSegment(q, float2(gap, 0.0), float2(gap + len, 0.0)),
+                        SdSegment(q, float2(0.0, gap), float2(0.0, gap + len)));
+                break;
+            }
+            case 2: {
+                d = abs(length(p) - gap);
+                break;
+            }
+            case 3: {
+                d = length(p);
+                halfWidth = gap;
+                break;
+            }
+            case 4: {
+                float2 q = float2(abs(p.x), p.y);
+                float arm = len * 0.70710678;
+                d = SdSegment(q, float2(0.0, gap), float2(arm, gap + arm));
+                break;
+            }
+            case 5: {
+                float2 q = float2(abs(p.x), p.y);
+                d = min(SdSegment(q, float2(gap, 0.0), float2(gap + len, 0.0)),
+                        SdSegment(p, float2(0.0, gap), float2(0.0, gap + len)));
+                break;
+            }
+        }
+
+        d -= halfWidth + grow;
+        alpha = max(alpha, saturate(0.5 - d / pixelSize));
+    }
+
+    return alpha;
+}
+
+// `grow` expands the crosshair shape, which is used to draw its outline.
+float CrosshairAlpha(float2 uv, float grow)
 {
-    float2 c = (uv - 0.5) * g_ErfpsCrosshairScaleReciprocal;
+    float2 c = (uv - 0.5 - g_Erfps.crosshairOffset) * g_Erfps.crosshairScaleReciprocal;
     float2 cScreen = c * float2(g_vCameraParam.x, 1.0) * g_dynamicScreenPercentage;
 
-    int crosshairKind = (g_ErfpsFlags >> 2) & 7;
+    int crosshairKind = ERFPS_FLAG(CROSSHAIR_KIND);
     switch (crosshairKind) {
         default:
             return 0.0;
         case 1: {
             cScreen = abs(cScreen);
-            return any(cScreen < 0.0013) && all(cScreen < 0.007) ? 1.0 : 0.0;
+            return any(cScreen < 0.0013 + grow) && all(cScreen < 0.007 + grow) ? 1.0 : 0.0;
         }
         case 2: {
-            float r = length(cScreen) / 0.0018;
+            float r = length(cScreen) / (0.0018 + grow);
             float d = fwidth(r);
             float a = 1.0 - smoothstep(1.0 - d, 1.0 + d, r);
             return a;
         }
         case 3: {
-            float2 r = length(cScreen) / float2(0.0066, 0.008);
+            float2 r = length(cScreen) / float2(0.0066 - grow, 0.008 + grow);
             float2 d = fwidth(r);
             float2 a = 1.0 - smoothstep(1.0 - d, 1.0 + d, r);
             return a.y - a.x;
         }
         case 4: {
-            float3 r = length(cScreen) / float3(0.0066, 0.008, 0.0012);
+            float3 r = length(cScreen) / float3(0.0066 - grow, 0.008 + grow, 0.0012 + grow);
             float3 d = fwidth(r);
             float3 a = 1.0 - smoothstep(1.0 - d, 1.0 + d, r);
             return a.y - a.x + a.z;
@@ -134,31 +313,104 @@ float CrosshairAlpha(float2 uv)
             cScreen = abs(cScreen);
             float s = cScreen.x + cScreen.y * 3.0;
             float d = fwidth(s);
-            float a = smoothstep(0.015 - d, 0.0175, s) - smoothstep(0.0175, 0.02 + d, s);
-            return cScreen.x > 0.007 ? a : 0.0;
+            float a = smoothstep(0.015 - grow - d, 0.0175, s) - smoothstep(0.0175, 0.02 + grow + d, s);
+            return cScreen.x > 0.007 - grow ? a : 0.0;
         }
+        case 6:
+            return CustomCrosshairAlpha(cScreen, grow);
+    }
+}
+
+// Flashed around the crosshair when an attack lands, fading out as the progress goes to 1.0.
+float HitMarkerAlpha(float2 uv)
+{
+    int style = ERFPS_FLAG(HIT_MARKER_STYLE);
+    if (style == 0) {
+        return 0.0;
+    }
+
+    float t = saturate(g_Erfps.hitMarkerProgress);
+
+    float2 c = (uv - 0.5 - g_Erfps.crosshairOffset) * g_Erfps.crosshairScaleReciprocal;
+    float2 cScreen = c * float2(g_vCameraParam.x, 1.0) * g_dynamicScreenPercentage;
+
+    float d;
+    if (style == 1) {
+        // Diagonal cross, moving outward.
+        float2 q = abs(float2(cScreen.x + cScreen.y, cScreen.x - cScreen.y)) * 0.70710678;
+        float gap = 0.006 + 0.003 * t;
+        d = min(SdSegment(q, float2(gap, 0.0), float2(gap + 0.006, 0.0)),
+            SdSegment(q, float2(0.0, gap), float2(0.0, gap + 0.006)));
+        d -= 0.0008;
+    } else {
+        // Expanding ring.
+        float radius = lerp(0.004, 0.016, t);
+        d = abs(length(cScreen) - radius) - 0.001;
     }
+
+    float w = fwidth(cScreen.y);
+    return saturate(0.5 - d / w) * (1.0 - t);
+}
+
+// Colors are packed as 8-bit RGBA with red in the lowest byte.
+float4 UnpackColor(uint color)
+{
+    return float4(color & 0xff, (color >> 8) & 0xff, (color >> 16) & 0xff, color >> 24) / 255.0;
+}
+
+// Darkens the edges of the screen, strength is computed CPU-side from camera movement.
+float Vignette(float2 uv)
+{
+    float2 aspect = float2(g_vCameraParam.x, 1.0);
+
+    // 0.0 at the center, 1.0 at the corners.
+    float r = length((uv - 0.5) * aspect) / length(0.5 * aspect);
+
+    float strength = g_Erfps.vignetteStrength;
+    float inner = lerp(1.0, 0.35, strength);
+
+    return 1.0 - strength * smoothstep(inner, inner + 0.45, r);
 }
 
 float4 PSMain(float4 position : SV_Position, float3 coord : TEXCOORD) : SV_TARGET
 {
     float2 xy = coord.xy;
 
-    float crosshairAlpha = CrosshairAlpha(xy);
-    if (crosshairAlpha > 0.001) {
-        // Draw crosshair.
-        float4 rgba = g_SourceTexture.SampleLevel(SS_ClampLinear, xy, 0);
-        rgba.rgb = lerp(rgba.rgb, 1.0 - rgba.rgb, crosshairAlpha);
-        return rgba;
+    // 0 - invert, 1 - solid, 2 - solid with an outline.
+    int blendMode = ERFPS_FLAG(CROSSHAIR_BLEND);
+
+    float4 color = UnpackColor(g_Erfps.crosshairColor);
+    float4 outlineColor = UnpackColor(g_Erfps.crosshairOutlineColor);
+
+    float crosshairAlpha = CrosshairAlpha(xy, 0.0) * g_Erfps.crosshairOpacity * color.a;
+    float outlineAlpha = 0.0;
+    if (blendMode == 2) {
+        outlineAlpha = CrosshairAlpha(xy, g_Erfps.crosshairOutlineWidth) * g_Erfps.crosshairOpacity * outlineColor.a;
     }
 
-    if (g_ErfpsFlags & 1) {
-        // Apply FOV correction.
-        if (g_ErfpsFlags & 2) {
-            xy = MapUvBarrel(xy);
+    float4 hitColor = UnpackColor(g_Erfps.hitMarkerColor);
+    float hitAlpha = HitMarkerAlpha(xy) * hitColor.a;
+
+    if (max(max(crosshairAlpha, outlineAlpha), hitAlpha) > 0.001) {
+        // Draw crosshair over the image as it appears with FOV correction.
+        float2 uv = ERFPS_FLAG(FOV_CORRECTION) ? MapUv(xy) : xy;
+        float2 texEdge = g_dynamicScreenPercentage - g_texSizeReciprocal * 0.5;
+        float4 rgba = g_SourceTexture.SampleLevel(SS_ClampLinear, min(uv * g_dynamicScreenPercentage, texEdge), 0);
+        if (blendMode == 0) {
+            rgba.rgb = lerp(rgba.rgb, 1.0 - rgba.rgb, crosshairAlpha);
         } else {
-            xy = MapUvFisheye(xy);
+            rgba.rgb = lerp(rgba.rgb, outlineColor.rgb, outlineAlpha);
+            rgba.rgb = lerp(rgba.rgb, color.rgb, crosshairAlpha);
         }
+        rgba.rgb = lerp(rgba.rgb, hitColor.rgb, hitAlpha);
+        return rgba;
+    }
+
+    float sharpening = 0.0;
+
+    if (ERFPS_FLAG(FOV_CORRECTION) && ERFPS_FLAG(SHARPENING)) {
+        // Full strength at 1.5x magnification.
+        sharpening = g_Erfps.sharpening * saturate(2.0 * (Magnification(xy) - 1.0));
     }
 
     float2 xy2m1 = xy * 2.0 - 1.0;
@@ -167,21 +419,50 @@ float4 PSMain(float4 position : SV_Position, float3 coord : TEXCOORD) : SV_TARGE
     float2 chromaG = g_vChromaticAberrationRG.zw;
     float2 chromaB = g_vChromaticAberrationB;
 
+    if (ERFPS_FLAG(CHROMATIC_ABERRATION_SCALE)) {
+        // Scale chromatic aberration.
+        chromaR *= g_Erfps.chromaticAberrationScale;
+        chromaG *= g_Erfps.chromaticAberrationScale;
+        chromaB *= g_Erfps.chromaticAberrationScale;
+    }
+
+    // Chromatic aberration offsets are computed in undistorted screen space
+    // and mapped with FOV correction after, keeping the fringing radially symmetric.
+    float2 xy2m1ChromaR = xy2m1 * chromaR + xy;
+    float2 xy2m1ChromaG = xy2m1 * chromaG + xy;
+    float2 xy2m1ChromaB = xy2m1 * chromaB + xy;
+
+    if (ERFPS_FLAG(FOV_CORRECTION)) {
+        // Apply FOV correction.
+        xy2m1ChromaR = MapUv(xy2m1ChromaR);
+        xy2m1ChromaG = MapUv(xy2m1ChromaG);
+        xy2m1ChromaB = MapUv(xy2m1ChromaB);
+    }
+
     float2 dynamicScreenPercentage = g_dynamicScreenPercentage;
     float2 texSizeReciprocal = g_texSizeReciprocal;
     float2 texEdge = dynamicScreenPercentage - texSizeReciprocal * 0.5;
 
-    float2 xy2m1ChromaR = xy2m1 * chromaR + xy;
     float2 rCoord = min(xy2m1ChromaR * dynamicScreenPercentage, texEdge);
     float r = g_SourceTexture.SampleLevel(SS_ClampLinear, rCoord, 0).r;
 
-    float2 xy2m1ChromaG = xy2m1 * chromaG + xy;
     float2 gCoord = min(xy2m1ChromaG * dynamicScreenPercentage, texEdge);
     float g = g_SourceTexture.SampleLevel(SS_ClampLinear, gCoord, 0).g;
     
-    float2 xy2m1ChromaB = xy2m1 * chromaB + xy;
     float2 bCoord = min(xy2m1ChromaB * dynamicScreenPercentage, texEdge);
     float b = g_SourceTexture.SampleLevel(SS_ClampLinear, bCoord, 0).b;
 
-    return float4(r, g, b, 1.0);
+    float3 rgb = float3(r, g, b);
+
+    if (sharpening > 0.001) {
+        // Sharpen the image where FOV correction magnifies it.
+        rgb = Sharpen(rgb, rCoord, gCoord, bCoord, texEdge, sharpening);
+    }
+
+    if (ERFPS_FLAG(VIGNETTE)) {
+        // Apply comfort vignette.
+        rgb *= Vignette(coord.xy);
+    }
+
+    return float4(rgb, 1.0);
 }
\ No newline at end of file
@@ -2,7 +2,7 @@ use std::sync::LazyLock;
 
 use serde::Deserialize;
 
-use crate::config::toml::TOML_STR;
+use crate::{config::toml::TOML_STR, player::WeaponCategory, shaders::crosshair::CrosshairShape};
 
 mod toml;
 pub mod updater;
@@ -12,6 +12,8 @@ pub mod updater;
 pub struct Config {
     pub fov: f32,
 
+    pub near_plane: NearPlane,
+
     pub angle_limit: [f32; 2],
 
     pub extra_player_height: f32,
@@ -24,6 +26,10 @@ pub struct Config {
 
     pub prioritize_lock_on: bool,
 
+    pub lock_on: LockOn,
+
+    pub aim_assist: AimAssist,
+
     pub unlocked_movement: bool,
 
     pub unobtrusive_dodges: bool,
@@ -32,8 +38,44 @@ pub struct Config {
 
     pub track_damage: bool,
 
+    pub damage_tracking: EasingCurve,
+
+    pub dodge_tracking: EasingCurve,
+
+    pub throw_tracking: EasingCurve,
+
+    pub tracking_roll_scale: f32,
+
+    pub max_tracked_pitch: f32,
+
+    pub chromatic_aberration_scale: f32,
+
     pub restricted_sprint: bool,
 
+    pub use_camera_collision: bool,
+
+    pub camera_collision_radius: f32,
+
+    pub camera_collision_filter: u32,
+
+    pub use_vignette: bool,
+
+    pub vignette_strength: f32,
+
+    pub vignette_angular_threshold: f32,
+
+    pub vignette_speed_threshold: f32,
+
+    pub vignette_fade_time: f32,
+
+    pub use_hit_marker: bool,
+
+    pub hit_marker_style: HitMarkerStyle,
+
+    pub hit_marker_duration: f32,
+
+    pub hit_marker_color: u32,
+
     pub use_stabilizer: bool,
 
     pub stabilizer_window: f32,
@@ -44,13 +86,39 @@ pub struct Config {
 
     pub crosshair_scale: (f32, f32),
 
+    pub crosshair_blend: CrosshairBlend,
+
+    pub crosshair_color: u32,
+
+    pub crosshair_outline_color: u32,
+
+    pub crosshair_outline_width: f32,
+
+    pub crosshair_shape: CrosshairShape,
+
+    pub weapon_crosshairs: WeaponCrosshairs,
+
+    pub hide_idle_crosshair: bool,
+
+    pub crosshair_fade_time: f32,
+
+    pub convergent_crosshair: bool,
+
     pub use_fov_correction: bool,
 
-    pub use_barrel_correction: bool,
+    pub fov_correction: FovCorrection,
 
     pub correction_strength: f32,
 
     pub correction_cylindricity: f32,
+
+    pub panini_distance: f32,
+
+    pub panini_vertical_compensation: f32,
+
+    pub fov_sharpening: f32,
+
+    pub hud_aspect_ratio: Option<f32>,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Deserialize)]
@@ -62,6 +130,164 @@ pub enum CrosshairKind {
     Circle,
     CircleDot,
     Angled,
+    Custom,
+}
+
+/// A part of a custom crosshair, sizes are in pixels at 1080p.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum CrosshairPrimitive {
+    /// Four lines around the center.
+    Lines {
+        gap: f32,
+        length: f32,
+        thickness: f32,
+    },
+    Ring {
+        radius: f32,
+        thickness: f32,
+    },
+    Dot {
+        radius: f32,
+    },
+    /// A "^" below the center, pointing up at it.
+    Chevron {
+        gap: f32,
+        length: f32,
+        thickness: f32,
+    },
+    /// Lines to the left, right and below the center.
+    T {
+        gap: f32,
+        length: f32,
+        thickness: f32,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CrosshairBlend {
+    Invert,
+    Solid,
+    SolidOutline,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FovCorrection {
+    None,
+    Fisheye,
+    Barrel,
+    Panini,
+    Stereographic,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HitMarkerStyle {
+    Cross,
+    Ring,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WeaponCrosshairs {
+    pub fist: CrosshairKind,
+    pub melee: CrosshairKind,
+    pub bow: CrosshairKind,
+    pub crossbow: CrosshairKind,
+    pub ballista: CrosshairKind,
+    pub staff: CrosshairKind,
+    pub seal: CrosshairKind,
+    pub shield: CrosshairKind,
+    pub torch: CrosshairKind,
+}
+
+impl WeaponCrosshairs {
+    pub fn get(&self, category: WeaponCategory) -> CrosshairKind {
+        match category {
+            WeaponCategory::Fist => self.fist,
+            WeaponCategory::Melee => self.melee,
+            WeaponCategory::Bow => self.bow,
+            WeaponCategory::Crossbow => self.crossbow,
+            WeaponCategory::Ballista => self.ballista,
+            WeaponCategory::Staff => self.staff,
+            WeaponCategory::Seal => self.seal,
+            WeaponCategory::Shield => self.shield,
+            WeaponCategory::Torch => self.torch,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AimAssist {
+    pub enabled: bool,
+    pub mouse: bool,
+    pub strength: f32,
+    pub slowdown: f32,
+    pub cone_angle: f32,
+    pub max_distance: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AimPoint {
+    Lock,
+    Head,
+    Chest,
+    Center,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NearPlane {
+    pub idle: f32,
+    pub attacking: f32,
+    pub guarding: f32,
+    pub riding: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LockOn {
+    pub flick_switch: bool,
+    pub auto_release: bool,
+    pub release_time: f32,
+    pub aim_point: AimPoint,
+    pub aim_offset: f32,
+    pub chase_curve: EasingCurve,
+    pub soft_cone_angle: f32,
+    pub soft_max_distance: f32,
+    pub angle_weight: f32,
+    pub distance_weight: f32,
+    pub occlusion_weight: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EasingCurve {
+    Power { exponent: f32, scale: f32 },
+    Exponential { time_constant: f32 },
+    Spring { omega: f32 },
+}
+
+impl EasingCurve {
+    /// The remaining distance at which a curve is considered settled.
+    pub const SETTLE_DISTANCE: f32 = 0.01;
+
+    pub fn power(exponent: f32, settle_time: f32) -> Self {
+        let scale = (1.0 - Self::SETTLE_DISTANCE.powf(exponent.recip())) / settle_time;
+        Self::Power { exponent, scale }
+    }
+
+    pub fn exponential(settle_time: f32) -> Self {
+        let time_constant = settle_time / -Self::SETTLE_DISTANCE.ln();
+        Self::Exponential { time_constant }
+    }
+
+    pub fn spring(settle_time: f32) -> Self {
+        // Solution to (1 + x) * e^(-x) = `SETTLE_DISTANCE`.
+        const SETTLE_OMEGA_TIME: f32 = 6.638352;
+        Self::Spring {
+            omega: SETTLE_OMEGA_TIME / settle_time,
+        }
+    }
 }
 
 impl From<toml::Config> for Config {
@@ -69,56 +295,228 @@ impl From<toml::Config> for Config {
         let degrees = config.fov.horizontal_fov.clamp(45.0, 130.0);
         let fov = degrees.to_radians();
 
+        let near_plane = NearPlane {
+            idle: config.near_plane.idle.clamp(0.01, 1.0),
+            attacking: config.near_plane.attacking.clamp(0.01, 1.0),
+            guarding: config.near_plane.guarding.clamp(0.01, 1.0),
+            riding: config.near_plane.riding.clamp(0.01, 1.0),
+        };
+
+        let lock_on = LockOn {
+            flick_switch: config.lock_on.flick_switch,
+            auto_release: config.lock_on.auto_release,
+            release_time: config.lock_on.release_time.clamp(0.1, 10.0),
+            aim_point: config.lock_on.aim_point,
+            aim_offset: config.lock_on.aim_offset.clamp(-1.0, 1.0),
+            chase_curve: config.lock_on.chase.into(),
+            soft_cone_angle: config.lock_on.soft_cone_angle.clamp(1.0, 45.0).to_radians(),
+            soft_max_distance: config.lock_on.soft_max_distance.clamp(2.0, 60.0),
+            angle_weight: config.lock_on.angle_weight.clamp(0.0, 10.0),
+            distance_weight: config.lock_on.distance_weight.clamp(0.0, 10.0),
+            occlusion_weight: config.lock_on.occlusion_weight.clamp(0.0, 10.0),
+        };
+
+        let aim_assist = AimAssist {
+            enabled: config.aim_assist.enabled,
+            mouse: config.aim_assist.mouse,
+            strength: config.aim_assist.strength.clamp(0.0, 1.0),
+            slowdown: config.aim_assist.slowdown.clamp(0.0, 1.0),
+            cone_angle: config.aim_assist.cone_angle.clamp(1.0, 15.0).to_radians(),
+            max_distance: config.aim_assist.max_distance.clamp(5.0, 100.0),
+        };
+
         let mut extra_player_height = config.player.height_multiplier.clamp(0.975, 1.05) - 1.0;
         if extra_player_height > 0.0 {
             extra_player_height *= 0.5;
         }
 
+        let camera_collision_radius = config.collision.probe_radius.clamp(0.01, 0.5);
+
+        let vignette_strength = config.vignette.strength.clamp(0.0, 1.0);
+        let vignette_angular_threshold = config.vignette.angular_threshold.clamp(10.0, 720.0);
+        let vignette_speed_threshold = config.vignette.speed_threshold.clamp(1.0, 30.0);
+        let vignette_fade_time = config.vignette.fade_time.clamp(0.05, 2.0);
+
+        let hit_marker_duration = config.hit_marker.duration.clamp(0.05, 1.0);
+        let hit_marker_color =
+            parse_color_or("hit_marker.color", &config.hit_marker.color, u32::MAX);
+
         let stabilizer_window = config.stabilizer.smoothing_window.clamp(0.1, 1.0);
         let stabilizer_factor = config.stabilizer.smoothing_factor.clamp(0.0, 1.0);
 
         let crosshair_scale_x = config.crosshair.scale_x.clamp(0.1, 4.0);
         let crosshair_scale_y = config.crosshair.scale_y.clamp(0.1, 4.0);
 
+        let kind = config.crosshair.kind;
+        let weapons = &config.crosshair.weapons;
+        let weapon_crosshairs = WeaponCrosshairs {
+            fist: weapons.fist.or_default(kind),
+            melee: weapons.melee.or_default(kind),
+            bow: weapons.bow.or_default(kind),
+            crossbow: weapons.crossbow.or_default(kind),
+            ballista: weapons.ballista.or_default(kind),
+            staff: weapons.staff.or_default(kind),
+            seal: weapons.seal.or_default(kind),
+            shield: weapons.shield.or_default(kind),
+            torch: weapons.torch.or_default(kind),
+        };
+
+        let (crosshair_color, crosshair_outline_color) = match config.crosshair.color_preset {
+            toml::ColorPreset::None => (
+                parse_color_or("crosshair.color", &config.crosshair.color, u32::MAX),
+                parse_color_or(
+                    "crosshair.outline_color",
+                    &config.crosshair.outline_color,
+                    0xff000000,
+                ),
+            ),
+            preset => preset.colors(),
+        };
+
+        // Pixels at 1080p to fractions of the screen height.
+        let crosshair_outline_width = config.crosshair.outline_width.clamp(0.0, 5.0) / 1080.0;
+
+        let crosshair_fade_time = config.crosshair.fade_time.clamp(0.0, 2.0);
+
         let correction_strength = config.fov.fov_correction_strength.clamp(0.0, 1.0);
         let correction_cylindricity =
             config.fov.fov_correction_cylindricity.clamp(0.0, 1.0) * 1.5 + 0.5;
 
-        let (use_fov_correction, use_barrel_correction) = match config.fov.fov_correction {
-            toml::FovCorrection::None => (false, false),
-            toml::FovCorrection::Fisheye => (true, false),
-            toml::FovCorrection::Barrel => (true, true),
-        };
+        let damage_tracking = config.tracking.damage.into();
+        let dodge_tracking = config.tracking.dodge.into();
+        let throw_tracking = config.tracking.throw.into();
+
+        let tracking_roll_scale = config.comfort.roll_scale.clamp(0.0, 1.0);
+        let max_tracked_pitch = config
+            .comfort
+            .max_tracked_pitch
+            .clamp(0.0, 90.0)
+            .to_radians();
+        let chromatic_aberration_scale = config.comfort.chromatic_aberration.clamp(0.0, 2.0);
+
+        let panini_distance = config.fov.panini_distance.clamp(0.0, 2.0);
+        let panini_vertical_compensation = config.fov.panini_vertical_compensation.clamp(0.0, 1.0);
+        let fov_sharpening = config.fov.sharpening.clamp(0.0, 1.0);
+        let hud_aspect_ratio = (config.fov.hud_aspect_ratio > 0.0)
+            .then(|| config.fov.hud_aspect_ratio.clamp(1.0, 4.0));
 
         Self {
             fov,
+            near_plane,
             angle_limit: const { [f32::to_radians(-80.0), f32::to_radians(70.0)] },
             extra_player_height,
             start_in_first_person: config.gameplay.start_in_first_person,
             show_tutorial: config.gameplay.show_tutorial,
             prioritize_lock_on: config.gameplay.prioritize_lock_on,
             soft_lock_on: config.gameplay.soft_lock_on,
+            lock_on,
+            aim_assist,
             unlocked_movement: config.gameplay.unlocked_movement,
             unobtrusive_dodges: config.gameplay.unobtrusive_dodges,
             track_dodges: config.gameplay.track_dodges,
             track_damage: config.gameplay.track_damage,
+            damage_tracking,
+            dodge_tracking,
+            throw_tracking,
+            tracking_roll_scale,
+            max_tracked_pitch,
+            chromatic_aberration_scale,
             restricted_sprint: config.gameplay.restricted_sprint,
+            use_camera_collision: config.collision.enabled,
+            camera_collision_radius,
+            camera_collision_filter: config.collision.collision_filter,
+            use_vignette: config.vignette.enabled,
+            vignette_strength,
+            vignette_angular_threshold: vignette_angular_threshold.to_radians(),
+            vignette_speed_threshold,
+            vignette_fade_time,
+            use_hit_marker: config.hit_marker.enabled,
+            hit_marker_style: config.hit_marker.style,
+            hit_marker_duration,
+            hit_marker_color,
             use_stabilizer: config.stabilizer.enabled,
             stabilizer_window,
             stabilizer_factor,
             crosshair: config.crosshair.kind,
             crosshair_scale: (crosshair_scale_x, crosshair_scale_y),
-            use_fov_correction,
-            use_barrel_correction,
+            crosshair_blend: config.crosshair.blend,
+            crosshair_color,
+            crosshair_outline_color,
+            crosshair_outline_width,
+            crosshair_shape: CrosshairShape::encode(&config.crosshair.shape),
+            weapon_crosshairs,
+            hide_idle_crosshair: config.crosshair.hide_when_idle,
+            crosshair_fade_time,
+            convergent_crosshair: config.crosshair.convergent,
+            use_fov_correction: config.fov.fov_correction != FovCorrection::None,
+            fov_correction: config.fov.fov_correction,
             correction_strength,
             correction_cylindricity,
+            panini_distance,
+            panini_vertical_compensation,
+            fov_sharpening,
+            hud_aspect_ratio,
+        }
+    }
+}
+
+impl Default for EasingCurve {
+    fn default() -> Self {
+        Self::Power {
+            exponent: 6.0,
+            scale: 1.0,
         }
     }
 }
 
+impl From<toml::Easing> for EasingCurve {
+    fn from(easing: toml::Easing) -> Self {
+        let settle_time = easing.settle_time.clamp(0.05, 4.0);
+
+        match easing.curve {
+            toml::EasingKind::Power => Self::power(easing.exponent.clamp(1.0, 10.0), settle_time),
+            toml::EasingKind::Exponential => Self::exponential(settle_time),
+            toml::EasingKind::Spring => Self::spring(settle_time),
+        }
+    }
+}
+
+/// Parses a "#RRGGBB" or "#RRGGBBAA" color, packed as 8-bit RGBA with red in the lowest byte.
+fn parse_color(color: &str) -> Option<u32> {
+    let hex = color.strip_prefix('#').unwrap_or(color);
+
+    let rgba = match hex.len() {
+        6 => u32::from_str_radix(hex, 16).ok()? << 8 | 0xff,
+        8 => u32::from_str_radix(hex, 16).ok()?,
+        _ => return None,
+    };
+
+    Some(rgba.swap_bytes())
+}
+
+/// Parses the color of `key`, warning about invalid colors and returning `default` for them.
+fn parse_color_or(key: &str, color: &str, default: u32) -> u32 {
+    parse_color(color).unwrap_or_else(|| {
+        log::warn!("invalid {key} {color:?} in config, expected \"#RRGGBB\" or \"#RRGGBBAA\"");
+        default
+    })
+}
+
 impl Default for Config {
     fn default() -> Self {
         static DEFAULT: LazyLock<Config> = LazyLock::new(|| ::toml::from_str(TOML_STR).unwrap());
         DEFAULT.clone()
     }
 }
+
+#[cfg(test)]
+#[test]
+fn check_parse_color() {
+    assert_eq!(parse_color("#ff8000"), Some(0xff0080ff));
+    assert_eq!(parse_color("#ff800080"), Some(0x800080ff));
+    assert_eq!(parse_color("ff8000"), Some(0xff0080ff));
+
+    assert_eq!(parse_color("#ff80"), None);
+    assert_eq!(parse_color("#gg8000"), None);
+    assert_eq!(parse_color("white"), None);
+}
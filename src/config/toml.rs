@@ -1,12 +1,22 @@
 use serde::Deserialize;
 
-use crate::config::CrosshairKind;
+use crate::config::{
+    AimPoint, CrosshairBlend, CrosshairKind, CrosshairPrimitive, FovCorrection, HitMarkerStyle,
+};
 
 #[derive(Debug, Deserialize)]
 pub struct Config {
     pub fov: Fov,
+    pub near_plane: NearPlane,
     pub gameplay: Gameplay,
+    pub lock_on: LockOn,
+    pub aim_assist: AimAssist,
     pub player: Player,
+    pub tracking: Tracking,
+    pub comfort: Comfort,
+    pub collision: Collision,
+    pub vignette: Vignette,
+    pub hit_marker: HitMarker,
     pub stabilizer: Stabilizer,
     pub crosshair: Crosshair,
 }
@@ -17,6 +27,18 @@ pub struct Fov {
     pub fov_correction: FovCorrection,
     pub fov_correction_strength: f32,
     pub fov_correction_cylindricity: f32,
+    pub panini_distance: f32,
+    pub panini_vertical_compensation: f32,
+    pub sharpening: f32,
+    pub hud_aspect_ratio: f32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NearPlane {
+    pub idle: f32,
+    pub attacking: f32,
+    pub guarding: f32,
+    pub riding: f32,
 }
 
 #[derive(Debug, Deserialize)]
@@ -32,11 +54,81 @@ pub struct Gameplay {
     pub restricted_sprint: bool,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct LockOn {
+    pub flick_switch: bool,
+    pub auto_release: bool,
+    pub release_time: f32,
+    pub aim_point: AimPoint,
+    pub aim_offset: f32,
+    pub soft_cone_angle: f32,
+    pub soft_max_distance: f32,
+    pub angle_weight: f32,
+    pub distance_weight: f32,
+    pub occlusion_weight: f32,
+    pub chase: Easing,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AimAssist {
+    pub enabled: bool,
+    pub mouse: bool,
+    pub strength: f32,
+    pub slowdown: f32,
+    pub cone_angle: f32,
+    pub max_distance: f32,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct Player {
     pub height_multiplier: f32,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct Tracking {
+    pub damage: Easing,
+    pub dodge: Easing,
+    pub throw: Easing,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Easing {
+    pub curve: EasingKind,
+    pub settle_time: f32,
+    pub exponent: f32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Comfort {
+    pub roll_scale: f32,
+    pub max_tracked_pitch: f32,
+    pub chromatic_aberration: f32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Collision {
+    pub enabled: bool,
+    pub probe_radius: f32,
+    pub collision_filter: u32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Vignette {
+    pub enabled: bool,
+    pub strength: f32,
+    pub angular_threshold: f32,
+    pub speed_threshold: f32,
+    pub fade_time: f32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HitMarker {
+    pub enabled: bool,
+    pub style: HitMarkerStyle,
+    pub duration: f32,
+    pub color: String,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct Stabilizer {
     pub enabled: bool,
@@ -49,32 +141,132 @@ pub struct Crosshair {
     pub kind: CrosshairKind,
     pub scale_x: f32,
     pub scale_y: f32,
+    pub convergent: bool,
+    pub blend: CrosshairBlend,
+    pub color_preset: ColorPreset,
+    pub color: String,
+    pub outline_color: String,
+    pub outline_width: f32,
+    pub hide_when_idle: bool,
+    pub fade_time: f32,
+    pub weapons: WeaponCrosshairs,
+    pub shape: Vec<CrosshairPrimitive>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WeaponCrosshairs {
+    pub fist: WeaponCrosshair,
+    pub melee: WeaponCrosshair,
+    pub bow: WeaponCrosshair,
+    pub crossbow: WeaponCrosshair,
+    pub ballista: WeaponCrosshair,
+    pub staff: WeaponCrosshair,
+    pub seal: WeaponCrosshair,
+    pub shield: WeaponCrosshair,
+    pub torch: WeaponCrosshair,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorPreset {
+    None,
+    HighContrast,
+    Deuteranopia,
+    Protanopia,
+    Tritanopia,
+}
+
+impl ColorPreset {
+    /// Crosshair and outline colors, packed as 8-bit RGBA with red in the lowest byte.
+    pub fn colors(self) -> (u32, u32) {
+        match self {
+            Self::None | Self::HighContrast => (0xffffffff, 0xff000000),
+            // Yellow and blue stay distinct with red-green color blindness.
+            Self::Deuteranopia | Self::Protanopia => (0xff00d7ff, 0xff6b2100),
+            // Red and teal stay distinct with blue-yellow color blindness.
+            Self::Tritanopia => (0xff3c3cff, 0xff2a2a00),
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Deserialize)]
 #[serde(rename_all = "lowercase")]
-pub enum FovCorrection {
+pub enum WeaponCrosshair {
+    Default,
     None,
-    Fisheye,
-    Barrel,
+    Cross,
+    Dot,
+    Circle,
+    CircleDot,
+    Angled,
+    Custom,
+}
+
+impl WeaponCrosshair {
+    pub fn or_default(self, default: CrosshairKind) -> CrosshairKind {
+        match self {
+            Self::Default => default,
+            Self::None => CrosshairKind::None,
+            Self::Cross => CrosshairKind::Cross,
+            Self::Dot => CrosshairKind::Dot,
+            Self::Circle => CrosshairKind::Circle,
+            Self::CircleDot => CrosshairKind::CircleDot,
+            Self::Angled => CrosshairKind::Angled,
+            Self::Custom => CrosshairKind::Custom,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EasingKind {
+    Power,
+    Exponential,
+    Spring,
 }
 
 const WITH_COMMENTS: &str = include_str!("../../dist/erfps2.toml");
 
+/// Position in erfps2.toml while stripping comments.
+#[derive(Clone, Copy)]
+enum StripState {
+    Value,
+    String,
+    Escape,
+    Comment,
+}
+
+impl StripState {
+    /// Returns the state after `byte`, and whether `byte` is kept.
+    ///
+    /// Comment lines are removed with their line break, and `#` in strings is kept.
+    const fn next(self, byte: u8) -> (Self, bool) {
+        match (self, byte) {
+            (Self::Value, b'#') => (Self::Comment, false),
+            (Self::Value, b'"') => (Self::String, true),
+            (Self::Value, _) => (Self::Value, true),
+            (Self::String, b'"' | b'\n') => (Self::Value, true),
+            (Self::String, b'\\') => (Self::Escape, true),
+            (Self::String, _) => (Self::String, true),
+            (Self::Escape, _) => (Self::String, true),
+            (Self::Comment, b'\n') => (Self::Value, false),
+            (Self::Comment, _) => (Self::Comment, false),
+        }
+    }
+}
+
 pub const TOML_STR: &str = {
     const STRIPPED_LEN: usize = {
         let bytes = WITH_COMMENTS.as_bytes();
         let mut i = 0;
         let mut len = 0;
-        let mut skip = false;
+        let mut state = StripState::Value;
         while i < bytes.len() {
-            let byte = bytes[i];
-            match (byte, skip) {
-                (b'#', _) => skip = true,
-                (b'\n', true) => skip = false,
-                (_, false) => len += 1,
-                _ => {}
+            let (next, keep) = state.next(bytes[i]);
+            if keep {
+                len += 1;
             }
+            state = next;
             i += 1;
         }
         len
@@ -84,18 +276,14 @@ pub const TOML_STR: &str = {
         let mut stripped = [b' '; STRIPPED_LEN];
         let mut i = 0;
         let mut j = 0;
-        let mut skip = false;
+        let mut state = StripState::Value;
         while i < bytes.len() {
-            let byte = bytes[i];
-            match (byte, skip) {
-                (b'#', _) => skip = true,
-                (b'\n', true) => skip = false,
-                (_, false) => {
-                    stripped[j] = byte;
-                    j += 1;
-                }
-                _ => {}
+            let (next, keep) = state.next(bytes[i]);
+            if keep {
+                stripped[j] = bytes[i];
+                j += 1;
             }
+            state = next;
             i += 1;
         }
         stripped
@@ -109,3 +297,13 @@ fn check_dist_config() {
     toml::from_str::<Config>(WITH_COMMENTS).unwrap();
     toml::from_str::<Config>(TOML_STR).unwrap();
 }
+
+#[cfg(test)]
+#[test]
+fn check_stripped_strings() {
+    let config = toml::from_str::<Config>(TOML_STR).unwrap();
+
+    assert_eq!(config.crosshair.color, "#ffffffff");
+    assert_eq!(config.crosshair.outline_color, "#000000ff");
+    assert!(!TOML_STR.contains("Possible value range"));
+}
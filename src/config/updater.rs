@@ -113,7 +113,7 @@ impl ConfigUpdater {
     }
 }
 
-fn current_module_path() -> Result<PathBuf, WinError> {
+pub(crate) fn current_module_path() -> Result<PathBuf, WinError> {
     let module_handle = unsafe {
         fn in_module_dummy() {}
         let mut module_handle = HMODULE::default();
@@ -8,8 +8,8 @@ use std::{
 };
 
 use eldenring::cs::{
-    CSActionButtonMan, CSEventFlagMan, CSRemo, ChrCam, ChrCamType, ChrExFollowCam, ChrIns,
-    FieldInsHandle, FieldInsType, GameDataMan, LockTgtMan, PlayerIns,
+    CSActionButtonMan, CSEventFlagMan, CSRemo, ChrCam, ChrCamType, ChrExFollowCam, FieldInsHandle,
+    GameDataMan, LockTgtMan, PlayerIns,
 };
 use fromsoftware_shared::{F32ViewMatrix, FromStatic};
 use glam::{EulerRot, Mat3A, Mat4, Quat, Vec3, Vec4};
@@ -17,18 +17,27 @@ use glam::{EulerRot, Mat3A, Mat4, Quat, Vec3, Vec4};
 use crate::{
     config::{Config, CrosshairKind, updater::ConfigUpdater},
     core::{
+        aim_assist::AimAssist,
         behavior::{BehaviorStateSet, BehaviorStates},
+        collision::{CameraCollision, CollisionArgs},
+        comfort::{Vignette, VignetteArgs, apply_tracking, scale_roll},
+        easing::Easing,
         frame_cached::FrameCached,
         head_tracker::HeadTracker,
+        hit_marker::HitMarker,
         time::{FrameTime, TransTime},
         world::{FromWorld, Void, World, WorldState},
     },
     game::GameDataManExt,
+    input::poll_input,
     player::PlayerExt,
     program::Program,
-    raycast::cast_sphere,
     rva::CAM_WALL_RECOVERY_RVA,
-    shaders::{enable_dithering, enable_fov_correction, enable_vfx_fade, set_crosshair},
+    shaders::{
+        enable_dithering, enable_fov_correction, enable_vfx_fade, set_aspect_ratio,
+        set_chromatic_aberration, set_crosshair, set_crosshair_shape, set_crosshair_style,
+        set_hit_marker, set_sharpening, set_vignette,
+    },
     tutorial::{TUTORIAL_EVENT_FLAG_ID, show_tutorial},
 };
 
@@ -36,9 +45,16 @@ pub use behavior::BehaviorState;
 
 pub mod world;
 
+mod aim_assist;
 mod behavior;
+mod collision;
+mod comfort;
+mod crosshair;
+mod easing;
 mod frame_cached;
 mod head_tracker;
+mod hit_marker;
+mod lock_on;
 mod stabilizer;
 mod time;
 
@@ -59,8 +75,16 @@ pub struct State {
     frame_time: FrameCached<FrameTime>,
     trans_time: FrameCached<TransTime>,
     head_tracker: FrameCached<HeadTracker>,
+    vignette: FrameCached<Vignette>,
+    camera_collision: FrameCached<CameraCollision>,
+    aim_assist: FrameCached<AimAssist>,
     behavior_states: BehaviorStates,
     saved_angle_limit: Option<f32>,
+    saved_near_plane: Option<f32>,
+    lock_lost_time: f32,
+    lock_chase: Easing,
+    crosshair_opacity: f32,
+    hit_marker: HitMarker,
 }
 
 impl CoreLogic {
@@ -144,14 +168,23 @@ where
         self.first_person && !in_cutscene() && !self.is_dist_view_cam()
     }
 
+    pub fn set_game_time(&mut self, time: f32) {
+        self.frame_time.set_game_time(time);
+    }
+
     pub fn next_frame(&mut self) {
         let frame_time = self.frame_time.measure();
 
+        poll_input();
+
         let stabilizer_window = self.config.stabilizer_window;
         self.head_tracker.set_stabilizer_window(stabilizer_window);
 
         self.trans_time.next_frame(frame_time);
         self.head_tracker.next_frame(frame_time);
+        self.vignette.next_frame(frame_time);
+        self.camera_collision.next_frame(frame_time);
+        self.aim_assist.next_frame(frame_time);
 
         self.update_fov_correction();
     }
@@ -186,15 +219,31 @@ where
             follow_cam.reset_camera_x = true;
         }
 
-        let frame_time = self.frame_time.get(());
-        if let Some(lock_tgt) = self.get::<LockTgtMan>() {
-            let lock_chase_rate = &mut follow_cam.lock_chase_rate;
-
-            if lock_tgt.is_locked_on && *lock_chase_rate <= 1.0 {
-                *lock_chase_rate = f32::min(*lock_chase_rate + frame_time, 1.0);
-            } else if *lock_chase_rate > 0.3 {
-                *lock_chase_rate = f32::max(*lock_chase_rate - frame_time, 0.3);
-            }
+        let frame_time = self.frame_time.get(()).real;
+        if let Some(is_locked_on) = self
+            .get::<LockTgtMan>()
+            .map(|lock_tgt| lock_tgt.is_locked_on)
+        {
+            const MIN_CHASE_RATE: f32 = 0.3;
+            const MAX_CHASE_RATE: f32 = 1.0;
+
+            let target = if is_locked_on {
+                MAX_CHASE_RATE
+            } else {
+                MIN_CHASE_RATE
+            };
+
+            // Normalized so that the settle time covers the whole range.
+            let range = MAX_CHASE_RATE - MIN_CHASE_RATE;
+            let lock_chase_rate = follow_cam
+                .lock_chase_rate
+                .clamp(MIN_CHASE_RATE, MAX_CHASE_RATE);
+            let distance = (lock_chase_rate - target) / range;
+
+            let curve = self.config.lock_on.chase_curve;
+            let step = self.lock_chase.step(curve, distance, frame_time);
+
+            follow_cam.lock_chase_rate = lock_chase_rate - step * range;
         }
     }
 
@@ -223,13 +272,29 @@ where
     }
 
     fn update_fov_correction(&self) {
+        if let Some(chr_cam) = self.get::<ChrCam>() {
+            set_aspect_ratio(chr_cam.pers_cam.aspect_ratio);
+        }
+
         enable_fov_correction(
             self.first_person && self.config.use_fov_correction,
+            self.config.fov_correction,
             self.config.correction_strength,
             self.config.correction_cylindricity,
-            self.config.use_barrel_correction,
+            (
+                self.config.panini_distance,
+                self.config.panini_vertical_compensation,
+            ),
             self.fov(),
         );
+
+        set_sharpening(self.config.fov_sharpening);
+
+        set_chromatic_aberration(if self.first_person {
+            self.config.chromatic_aberration_scale
+        } else {
+            1.0
+        });
     }
 
     fn set_crosshair_if(&self, cond: bool) {
@@ -238,12 +303,22 @@ where
         };
 
         let crosshair = if cond && is_hud_enabled {
-            self.config.crosshair
+            self.get::<PlayerIns>()
+                .map_or(self.config.crosshair, |player| {
+                    self.config.weapon_crosshairs.get(player.weapon_category())
+                })
         } else {
             CrosshairKind::None
         };
 
         set_crosshair(crosshair, self.config.crosshair_scale);
+        set_crosshair_style(
+            self.config.crosshair_blend,
+            self.config.crosshair_color,
+            self.config.crosshair_outline_color,
+            self.config.crosshair_outline_width,
+        );
+        set_crosshair_shape(&self.config.crosshair_shape);
     }
 
     fn is_aim_cam(&self) -> bool {
@@ -305,16 +380,24 @@ impl<'s> CoreLogicContext<'_, World<'s>> {
     pub fn camera_position(&mut self) -> F32ViewMatrix {
         let camera_rotation = Quat::from_mat3a(&self.chr_cam.pers_cam.matrix.rotation());
 
+        let camera_origin = Vec4::from(self.chr_cam.pers_cam.matrix.3).truncate();
+        let camera_rotation = self.apply_aim_assist(camera_rotation, camera_origin);
+
+        let config = self.config;
+
         let tracker_args = (&*self).into();
         let output = self.head_tracker.get(tracker_args);
 
-        let head_rotation = output.head_matrix.rotation::<Mat3A>();
+        let head_rotation = Quat::from_mat3a(&output.head_matrix.rotation());
+        let head_rotation = Mat3A::from_quat(scale_roll(head_rotation, config.tracking_roll_scale));
         let mut head_position = output.stabilized_head_position;
 
-        let camera_rotation = camera_rotation * output.tracking_rotation;
-
-        let cam_pitch = camera_rotation.to_euler(EulerRot::ZXY).1;
-        let cam_pitch_exp = (cam_pitch.abs() / 3.0).powi(2);
+        let camera_rotation = apply_tracking(
+            camera_rotation,
+            output.tracking_rotation,
+            config.tracking_roll_scale,
+            config.max_tracked_pitch,
+        );
 
         let (head_roll, head_pitch, _) = output
             .head_matrix
@@ -324,6 +407,11 @@ impl<'s> CoreLogicContext<'_, World<'s>> {
         let head_upright =
             ((1.05 - head_pitch.abs() / PI) * (1.05 - head_roll.abs() / PI)).clamp(0.0, 1.0);
 
+        let camera_rotation = self.lock_aim_correction(head_position) * camera_rotation;
+
+        let cam_pitch = camera_rotation.to_euler(EulerRot::ZXY).1;
+        let cam_pitch_exp = (cam_pitch.abs() / 3.0).powi(2);
+
         let world_contrib = Vec3::new(0.0, 0.1, 0.0);
         let head_contrib = Vec3::new(0.0, -0.1 * head_upright, -0.05);
         let cam_contrib =
@@ -333,6 +421,19 @@ impl<'s> CoreLogicContext<'_, World<'s>> {
             + head_rotation.transpose() * head_contrib
             + camera_rotation.inverse() * cam_contrib;
 
+        if config.use_camera_collision {
+            // Probe from the player's vertical axis, at the height of the camera.
+            let player_position = self.player.model_matrix().translation();
+            let origin = Vec3::new(player_position.x, head_position.y, player_position.z);
+
+            head_position = self.camera_collision.get(CollisionArgs {
+                origin,
+                camera_position: head_position,
+                radius: config.camera_collision_radius,
+                filter: config.camera_collision_filter,
+            });
+        }
+
         Mat4::from_rotation_translation(camera_rotation, head_position).into()
     }
 
@@ -372,9 +473,19 @@ impl<'s> CoreLogicContext<'_, World<'s>> {
         );
 
         if !first_person {
+            set_vignette(0.0);
+            set_hit_marker(None, 0.0, self.config.hit_marker_color);
+
+            if let Some(near_plane) = self.saved_near_plane.take() {
+                self.cs_cam.pers_cam_1.near_plane = near_plane;
+                self.chr_cam.pers_cam.near_plane = near_plane;
+            }
+
             return;
         }
 
+        self.update_crosshair_opacity();
+
         self.player.enable_face_model(false);
         self.player.enable_sheathed_weapons(false);
 
@@ -393,6 +504,10 @@ impl<'s> CoreLogicContext<'_, World<'s>> {
             self.soft_lock_on(camera_pos);
         }
 
+        self.update_vignette(camera_pos);
+        self.update_crosshair_offset(camera_pos);
+        self.update_hit_marker(camera_pos);
+
         self.cs_cam.pers_cam_1.matrix = camera_pos;
         self.chr_cam.pers_cam.matrix = camera_pos;
 
@@ -400,6 +515,28 @@ impl<'s> CoreLogicContext<'_, World<'s>> {
 
         self.cs_cam.pers_cam_1.fov = fov;
         self.chr_cam.pers_cam.fov = fov;
+
+        let near_plane = self.near_plane();
+
+        let original_near_plane = self.chr_cam.pers_cam.near_plane;
+        self.saved_near_plane.get_or_insert(original_near_plane);
+
+        self.cs_cam.pers_cam_1.near_plane = near_plane;
+        self.chr_cam.pers_cam.near_plane = near_plane;
+    }
+
+    fn near_plane(&self) -> f32 {
+        let near_plane = &self.config.near_plane;
+
+        if self.player.is_riding() {
+            near_plane.riding
+        } else if self.has_state(BehaviorState::Guard) {
+            near_plane.guarding
+        } else if self.has_state(BehaviorState::Attack) {
+            near_plane.attacking
+        } else {
+            near_plane.idle
+        }
     }
 
     pub fn update_chr_model_pos(&mut self) {
@@ -416,6 +553,15 @@ impl<'s> CoreLogicContext<'_, World<'s>> {
         self.player.chr_ctrl.scale_size_y = player_scale;
     }
 
+    /// Checks if the player is aiming or casting with a ranged weapon or catalyst.
+    ///
+    /// Drawing, firing and casting run in the attack behavior state, aiming
+    /// a bow or crossbow also switches to an aim camera.
+    pub fn is_aiming(&self) -> bool {
+        self.player.has_ranged_weapon()
+            && (self.has_state(BehaviorState::Attack) || self.is_aim_cam())
+    }
+
     pub fn is_player_sprinting(&self) -> bool {
         if self.config.restricted_sprint {
             self.player.is_sprinting()
@@ -482,6 +628,11 @@ impl<'s> CoreLogicContext<'_, World<'s>> {
             self.chr_cam.ex_follow_cam.max_lock_target_offset = 0.05;
 
             self.player.chr_ctrl.scale_size_y = 1.0;
+
+            if let Some(near_plane) = self.saved_near_plane.take() {
+                self.cs_cam.pers_cam_1.near_plane = near_plane;
+                self.chr_cam.pers_cam.near_plane = near_plane;
+            }
         } else {
             self.chr_cam.ex_follow_cam.max_lock_target_offset = 0.0;
         }
@@ -517,34 +668,22 @@ impl<'s> CoreLogicContext<'_, World<'s>> {
         self.behavior_states.push_state_set(behavior_set);
     }
 
-    fn soft_lock_on(&mut self, camera_pos: F32ViewMatrix) {
-        self.lock_tgt.lock_camera = false;
-
-        if !self.lock_tgt.is_lock_on_requested {
+    fn update_vignette(&mut self, camera_pos: F32ViewMatrix) {
+        if !self.config.use_vignette {
+            set_vignette(0.0);
             return;
         }
 
-        let origin = Vec4::from(camera_pos.3).truncate();
-        let direction = Vec4::from(camera_pos.2).truncate() * 20.0;
-
-        let hit = cast_sphere(origin, direction, 0.2, 0x2000058, |hit| {
-            hit.field_ins().is_none_or(|owner| {
-                if unsafe {
-                    owner.as_ref().handle.selector.field_ins_type() == Some(FieldInsType::Chr)
-                } {
-                    self.player.can_target(owner.as_ptr() as *const ChrIns)
-                } else {
-                    true
-                }
-            })
-        });
+        let args = VignetteArgs {
+            camera_rotation: Quat::from_mat3a(&camera_pos.rotation()),
+            player_position: self.player.model_matrix().translation(),
+            max_strength: self.config.vignette_strength,
+            angular_threshold: self.config.vignette_angular_threshold,
+            speed_threshold: self.config.vignette_speed_threshold,
+            fade_time: self.config.vignette_fade_time,
+        };
 
-        if let Some(hit) = hit
-            && let Some(field_ins_handle) = hit.field_ins_handle()
-            && field_ins_handle.selector.field_ins_type() == Some(FieldInsType::Chr)
-        {
-            self.lock_on_to(field_ins_handle);
-        }
+        set_vignette(self.vignette.get(args));
     }
 
     fn can_show_tutorial(&self) -> bool {
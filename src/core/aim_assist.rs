@@ -0,0 +1,312 @@
+use std::f32::consts::{PI, TAU};
+
+use glam::{EulerRot, Quat, Vec2, Vec3};
+
+use crate::{
+    core::{CoreLogicContext, frame_cached::FrameCache, time::FrameDelta, world::World},
+    input::is_gamepad_active,
+    player::PlayerExt,
+    raycast::has_line_of_sight,
+};
+
+/// Light aim assist for ranged weapons, applied as a yaw and pitch offset
+/// on top of the camera rotation.
+#[derive(Default)]
+pub struct AimAssist {
+    last: Option<Vec2>,
+    offset: Vec2,
+}
+
+pub struct AimAssistArgs {
+    pub camera_rotation: Quat,
+    pub origin: Vec3,
+    /// Centers of the targetable characters, empty while aim assist is inactive.
+    pub targets: Vec<Vec3>,
+    pub is_visible: fn(Vec3, Vec3) -> bool,
+    pub strength: f32,
+    pub slowdown: f32,
+    pub cone_angle: f32,
+    pub max_distance: f32,
+}
+
+impl AimAssist {
+    // The time constant for returning the offset to zero without a target.
+    const DECAY_TIME: f32 = 0.25;
+
+    // The rate at which the camera is pulled toward a target at full strength.
+    const PULL_RATE: f32 = 4.0;
+
+    fn rotation(&self, args: &AimAssistArgs) -> Quat {
+        let (yaw, pitch, roll) = args.camera_rotation.to_euler(EulerRot::YXZ);
+        Quat::from_euler(
+            EulerRot::YXZ,
+            yaw + self.offset.x,
+            pitch + self.offset.y,
+            roll,
+        )
+    }
+}
+
+impl FrameCache for AimAssist {
+    type Input = AimAssistArgs;
+    type Output<'a> = Quat;
+
+    fn update(&mut self, frame_time: FrameDelta, args: Self::Input) -> Self::Output<'_> {
+        let (yaw, pitch, _) = args.camera_rotation.to_euler(EulerRot::YXZ);
+        let angles = Vec2::new(yaw, pitch);
+
+        let delta = self
+            .last
+            .map_or(Vec2::ZERO, |last| wrap_angles(angles - last));
+        self.last = Some(angles);
+
+        let dt = frame_time.game;
+
+        let target_direction = select_target(args.camera_rotation * Vec3::Z, &args);
+
+        match target_direction.and_then(Vec3::try_normalize) {
+            Some(direction) => {
+                let target = Vec2::new(
+                    direction.x.atan2(direction.z),
+                    (-direction.y).atan2(direction.x.hypot(direction.z)),
+                );
+
+                let error = wrap_angles(target - (angles + self.offset));
+                let closeness = (1.0 - error.length() / args.cone_angle).clamp(0.0, 1.0);
+
+                // Counter part of the player's own camera movement near the target.
+                self.offset -= delta * args.slowdown * closeness;
+
+                let pull = (args.strength * Self::PULL_RATE * dt).min(1.0);
+                self.offset += error * pull;
+
+                self.offset = self.offset.clamp_length_max(args.cone_angle);
+            }
+            None => {
+                self.offset *= f32::exp(-dt / Self::DECAY_TIME);
+            }
+        }
+
+        self.rotation(&args)
+    }
+
+    fn get_cached(&mut self, _frame_time: FrameDelta, args: Self::Input) -> Self::Output<'_> {
+        self.rotation(&args)
+    }
+
+    fn reset(&mut self) {
+        self.last = None;
+        self.offset = Vec2::ZERO;
+    }
+}
+
+impl CoreLogicContext<'_, World<'_>> {
+    /// Applies aim assist to the camera rotation while aiming a ranged weapon or casting.
+    pub fn apply_aim_assist(&mut self, camera_rotation: Quat, origin: Vec3) -> Quat {
+        let config = self.config;
+
+        if !config.aim_assist.enabled {
+            return camera_rotation;
+        }
+
+        let is_active = self.is_aiming()
+            && (config.soft_lock_on || !self.lock_tgt.is_locked_on)
+            && (config.aim_assist.mouse || is_gamepad_active());
+
+        let targets = if is_active {
+            self.lock_targets()
+                .into_iter()
+                .filter(|target| target.chr().is_some_and(|chr| self.player.can_target(chr)))
+                .map(|target| target.center())
+                .collect()
+        } else {
+            vec![]
+        };
+
+        self.aim_assist.get(AimAssistArgs {
+            camera_rotation,
+            origin,
+            targets,
+            is_visible: has_line_of_sight,
+            strength: config.aim_assist.strength,
+            slowdown: config.aim_assist.slowdown,
+            cone_angle: config.aim_assist.cone_angle,
+            max_distance: config.aim_assist.max_distance,
+        })
+    }
+}
+
+/// Finds the direction to the visible target closest to the crosshair.
+///
+/// Line of sight is only checked for targets in the cone and range, once per frame.
+fn select_target(forward: Vec3, args: &AimAssistArgs) -> Option<Vec3> {
+    args.targets
+        .iter()
+        .filter_map(|&center| {
+            let direction = center - args.origin;
+            let angle = target_angle(forward, direction, args.cone_angle, args.max_distance)?;
+
+            (args.is_visible)(args.origin, center).then_some((direction, angle))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(direction, _)| direction)
+}
+
+/// Returns the angle between the crosshair and a target in the aim assist cone and range.
+fn target_angle(forward: Vec3, direction: Vec3, cone_angle: f32, max_distance: f32) -> Option<f32> {
+    let angle = forward.angle_between(direction);
+
+    (angle <= cone_angle && direction.length() <= max_distance).then_some(angle)
+}
+
+fn wrap_angles(angles: Vec2) -> Vec2 {
+    (angles + PI).rem_euclid(Vec2::splat(TAU)) - PI
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{EulerRot, Quat, Vec3};
+
+    use super::{AimAssist, AimAssistArgs, select_target, target_angle};
+    use crate::core::{frame_cached::FrameCache, time::FrameDelta};
+
+    const CONE_ANGLE: f32 = 0.2;
+    const MAX_DISTANCE: f32 = 30.0;
+
+    fn args(yaw: f32, targets: &[Vec3], strength: f32, slowdown: f32) -> AimAssistArgs {
+        AimAssistArgs {
+            camera_rotation: Quat::from_euler(EulerRot::YXZ, yaw, 0.0, 0.0),
+            origin: Vec3::ZERO,
+            targets: targets.to_vec(),
+            is_visible: |_, _| true,
+            strength,
+            slowdown,
+            cone_angle: CONE_ANGLE,
+            max_distance: MAX_DISTANCE,
+        }
+    }
+
+    fn at_yaw(yaw: f32, distance: f32) -> Vec3 {
+        Vec3::new(yaw.sin(), 0.0, yaw.cos()) * distance
+    }
+
+    fn yaw_offset(aim_assist: &mut AimAssist, args: AimAssistArgs) -> f32 {
+        let yaw = args.camera_rotation.to_euler(EulerRot::YXZ).0;
+        let rotation = aim_assist.update(FrameDelta::FRAME_60, args);
+
+        rotation.to_euler(EulerRot::YXZ).0 - yaw
+    }
+
+    #[test]
+    fn scores_targets_by_angle() {
+        let ahead = target_angle(Vec3::Z, Vec3::new(0.0, 0.0, 10.0), CONE_ANGLE, MAX_DISTANCE);
+        let aside = target_angle(Vec3::Z, Vec3::new(1.0, 0.0, 10.0), CONE_ANGLE, MAX_DISTANCE);
+        let far_aside = target_angle(Vec3::Z, Vec3::new(2.0, 0.0, 20.0), CONE_ANGLE, MAX_DISTANCE);
+
+        assert!(ahead.is_some_and(|angle| angle < 1e-3));
+        assert!(aside.is_some_and(|angle| (angle - 0.1f32.atan()).abs() < 1e-3));
+
+        // Only the angle matters, not the distance.
+        assert!((aside.unwrap() - far_aside.unwrap()).abs() < 1e-3);
+    }
+
+    #[test]
+    fn ignores_targets_outside_of_the_cone() {
+        let outside = Vec3::new(10.0, 0.0, 10.0);
+        assert_eq!(
+            target_angle(Vec3::Z, outside, CONE_ANGLE, MAX_DISTANCE),
+            None
+        );
+
+        let behind = Vec3::new(0.0, 0.0, -10.0);
+        assert_eq!(
+            target_angle(Vec3::Z, behind, CONE_ANGLE, MAX_DISTANCE),
+            None
+        );
+
+        let too_far = Vec3::new(0.0, 0.0, MAX_DISTANCE + 1.0);
+        assert_eq!(
+            target_angle(Vec3::Z, too_far, CONE_ANGLE, MAX_DISTANCE),
+            None
+        );
+    }
+
+    #[test]
+    fn selects_the_target_closest_to_the_crosshair() {
+        let near_but_aside = at_yaw(0.15, 5.0);
+        let far_but_centered = at_yaw(0.02, 25.0);
+        let out_of_range = at_yaw(0.0, MAX_DISTANCE + 5.0);
+        let out_of_cone = at_yaw(0.5, 10.0);
+
+        let args = args(
+            0.0,
+            &[near_but_aside, far_but_centered, out_of_range, out_of_cone],
+            1.0,
+            0.0,
+        );
+
+        assert_eq!(select_target(Vec3::Z, &args), Some(far_but_centered));
+    }
+
+    #[test]
+    fn skips_occluded_targets() {
+        let centered = at_yaw(0.0, 10.0);
+        let aside = at_yaw(0.1, 10.0);
+
+        let mut args = args(0.0, &[centered, aside], 1.0, 0.0);
+        args.is_visible = |_, to| to.x > 0.5;
+
+        assert_eq!(select_target(Vec3::Z, &args), Some(aside));
+
+        args.is_visible = |_, _| false;
+        assert_eq!(select_target(Vec3::Z, &args), None);
+    }
+
+    #[test]
+    fn pull_scales_with_strength() {
+        let target = [at_yaw(0.1, 10.0)];
+
+        let pulls = [0.0, 0.3, 1.0].map(|strength| {
+            let mut aim_assist = AimAssist::default();
+            yaw_offset(&mut aim_assist, args(0.0, &target, strength, 0.0))
+        });
+
+        assert!(pulls[0].abs() < 1e-6);
+        assert!(0.0 < pulls[1] && pulls[1] < pulls[2] && pulls[2] < 0.1);
+    }
+
+    #[test]
+    fn slowdown_falls_off_away_from_the_target() {
+        let slowdown = |target_yaw: f32| {
+            let target = [at_yaw(target_yaw, 10.0)];
+            let mut aim_assist = AimAssist::default();
+
+            yaw_offset(&mut aim_assist, args(0.0, &target, 0.0, 1.0));
+
+            // The player turns the camera, part of which is countered near the target.
+            -yaw_offset(&mut aim_assist, args(0.01, &target, 0.0, 1.0))
+        };
+
+        let near = slowdown(0.01);
+        let far = slowdown(0.15);
+
+        assert!(0.0 < far && far < near && near <= 0.01);
+    }
+
+    #[test]
+    fn offset_decays_without_targets() {
+        let mut aim_assist = AimAssist::default();
+
+        for _ in 0..10 {
+            yaw_offset(&mut aim_assist, args(0.0, &[at_yaw(0.1, 10.0)], 1.0, 0.0));
+        }
+
+        assert!(yaw_offset(&mut aim_assist, args(0.0, &[], 1.0, 0.0)) > 0.01);
+
+        for _ in 0..120 {
+            yaw_offset(&mut aim_assist, args(0.0, &[], 1.0, 0.0));
+        }
+
+        assert!(yaw_offset(&mut aim_assist, args(0.0, &[], 1.0, 0.0)).abs() < 1e-3);
+    }
+}
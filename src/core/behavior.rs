@@ -7,6 +7,7 @@ pub enum BehaviorState {
     Damage,
     Evasion,
     Gesture,
+    Guard,
 }
 
 #[derive(Clone, Copy, Default)]
@@ -43,6 +44,7 @@ impl BehaviorState {
             "Damage_SM" => Some(Self::Damage),
             "Evasion_SM" | "Stealth_Rolling_CMSG" => Some(Self::Evasion),
             "Gesture_SM" => Some(Self::Gesture),
+            "Guard_SM" => Some(Self::Guard),
             _ => None,
         }
     }
@@ -0,0 +1,83 @@
+use glam::Vec3;
+
+use crate::{
+    core::{frame_cached::FrameCache, time::FrameDelta},
+    raycast::cast_sphere,
+};
+
+/// Keeps the first person camera out of walls by probing for collisions
+/// from a point inside the player's body toward the camera position.
+///
+/// The camera is eased in while it stays within half the probe radius of the hit,
+/// where it is still clear of the wall, and snaps in further than that.
+pub struct CameraCollision {
+    fraction: f32,
+}
+
+pub struct CollisionArgs {
+    pub origin: Vec3,
+    pub camera_position: Vec3,
+    pub radius: f32,
+    pub filter: u32,
+}
+
+impl CameraCollision {
+    // The time constant for easing the camera in on a collision.
+    const PULL_IN_TIME: f32 = 0.04;
+
+    // The time constant for easing the camera back out after a collision.
+    const RELEASE_TIME: f32 = 0.1;
+
+    fn position(&self, args: &CollisionArgs) -> Vec3 {
+        args.origin.lerp(args.camera_position, self.fraction)
+    }
+}
+
+impl FrameCache for CameraCollision {
+    type Input = CollisionArgs;
+    type Output<'a> = Vec3;
+
+    fn update(&mut self, frame_time: FrameDelta, args: Self::Input) -> Self::Output<'_> {
+        let direction = args.camera_position - args.origin;
+
+        // Ignore characters, including the player.
+        let hit = cast_sphere(args.origin, direction, args.radius, args.filter, |hit| {
+            !hit.is_chr()
+        });
+
+        let target = hit.map_or(1.0, |hit| hit.segment.clamp(0.0, 1.0));
+
+        if target < self.fraction {
+            let length = direction.length();
+            let margin = if length > 0.0 {
+                0.5 * args.radius / length
+            } else {
+                0.0
+            };
+
+            let rate = 1.0 - f32::exp(-frame_time.game / Self::PULL_IN_TIME);
+            let eased = self.fraction + (target - self.fraction) * rate;
+
+            self.fraction = eased.min(target + margin);
+        } else {
+            let rate = 1.0 - f32::exp(-frame_time.game / Self::RELEASE_TIME);
+            self.fraction += (target - self.fraction) * rate;
+        }
+
+        self.position(&args)
+    }
+
+    fn get_cached(&mut self, _frame_time: FrameDelta, args: Self::Input) -> Self::Output<'_> {
+        self.position(&args)
+    }
+
+    fn reset(&mut self) {
+        self.fraction = 1.0;
+    }
+}
+
+impl Default for CameraCollision {
+    fn default() -> Self {
+        Self { fraction: 1.0 }
+    }
+}
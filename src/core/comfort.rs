@@ -0,0 +1,259 @@
+use std::f32::consts::FRAC_PI_2;
+
+use glam::{Quat, Vec2, Vec3};
+
+use crate::core::{frame_cached::FrameCache, time::FrameDelta};
+
+/// Peripheral vignette strength driven by camera and player movement.
+#[derive(Default)]
+pub struct Vignette {
+    last: Option<(Quat, Vec3)>,
+    strength: f32,
+}
+
+pub struct VignetteArgs {
+    pub camera_rotation: Quat,
+    pub player_position: Vec3,
+    pub max_strength: f32,
+    pub angular_threshold: f32,
+    pub speed_threshold: f32,
+    pub fade_time: f32,
+}
+
+/// Applies the head `tracking` rotation to the `camera` rotation, scaling the roll
+/// it introduces by `roll_scale` and limiting the pitch it introduces to `max_pitch`.
+///
+/// The tracking rotation is split into a twist about the view axis and a swing of the view
+/// axis in the camera's local frame, which stay well defined when looking straight up or down.
+pub fn apply_tracking(camera: Quat, tracking: Quat, roll_scale: f32, max_pitch: f32) -> Quat {
+    if roll_scale >= 1.0 && max_pitch >= FRAC_PI_2 {
+        return camera * tracking;
+    }
+
+    let (swing, twist) = swing_twist(tracking, Vec3::Z);
+    let twist = Quat::IDENTITY.slerp(twist, roll_scale);
+
+    let forward = swing * Vec3::Z;
+    let pitch = forward.y.clamp(-1.0, 1.0).asin();
+
+    let swing = if pitch.abs() > max_pitch {
+        let heading = Vec2::new(forward.x, forward.z).normalize_or(Vec2::Y);
+        let (sin, cos) = pitch.clamp(-max_pitch, max_pitch).sin_cos();
+
+        Quat::from_rotation_arc(Vec3::Z, Vec3::new(heading.x * cos, sin, heading.y * cos))
+    } else {
+        swing
+    };
+
+    camera * swing * twist
+}
+
+/// Scales the roll of `rotation` relative to the horizon by `roll_scale`.
+///
+/// Roll is undefined when looking straight up or down, so leveling fades out near vertical.
+pub fn scale_roll(rotation: Quat, roll_scale: f32) -> Quat {
+    // The horizontal length of the forward vector below which leveling fades out (~10 degrees).
+    const FADE_COS_PITCH: f32 = 0.17;
+
+    if roll_scale >= 1.0 {
+        return rotation;
+    }
+
+    let forward = rotation * Vec3::Z;
+    let level_right = Vec3::Y.cross(forward);
+    let fade = (level_right.length() / FADE_COS_PITCH).min(1.0);
+
+    let Some(level_right) = level_right.try_normalize() else {
+        return rotation;
+    };
+
+    let right = rotation * Vec3::X;
+    let roll = f32::atan2(
+        level_right.cross(right).dot(forward),
+        level_right.dot(right),
+    );
+
+    Quat::from_axis_angle(forward, -roll * (1.0 - roll_scale) * fade) * rotation
+}
+
+/// Splits `rotation` into a swing and a twist about `axis`, `rotation = swing * twist`.
+fn swing_twist(rotation: Quat, axis: Vec3) -> (Quat, Quat) {
+    let projection = axis * axis.dot(rotation.xyz());
+    let twist = Quat::from_xyzw(projection.x, projection.y, projection.z, rotation.w);
+
+    // A half turn swing leaves the twist undefined.
+    let twist = if twist.length_squared() > 1e-12 {
+        twist.normalize()
+    } else {
+        Quat::IDENTITY
+    };
+
+    (rotation * twist.inverse(), twist)
+}
+
+impl Vignette {
+    // Faster movement is assumed to be a teleport or a loading screen.
+    const MAX_PLAYER_SPEED: f32 = 50.0;
+
+    /// The vignette is applied past the thresholds and reaches full strength at twice them.
+    fn target_strength(&self, frame_time: f32, args: &VignetteArgs) -> f32 {
+        let Some((last_rotation, last_position)) = self.last else {
+            return 0.0;
+        };
+
+        let angular_speed = last_rotation.angle_between(args.camera_rotation) / frame_time;
+
+        let player_speed = last_position.distance(args.player_position) / frame_time;
+        let player_speed = if player_speed < Self::MAX_PLAYER_SPEED {
+            player_speed
+        } else {
+            0.0
+        };
+
+        let motion = f32::max(
+            angular_speed / args.angular_threshold,
+            player_speed / args.speed_threshold,
+        );
+
+        args.max_strength * (motion - 1.0).clamp(0.0, 1.0)
+    }
+}
+
+impl FrameCache for Vignette {
+    type Input = VignetteArgs;
+    type Output<'a> = f32;
+
+    fn update(&mut self, frame_time: FrameDelta, args: Self::Input) -> Self::Output<'_> {
+        let frame_time = frame_time.real;
+
+        if frame_time > 0.0 {
+            let target = self.target_strength(frame_time, &args);
+            let rate = 1.0 - f32::exp(-frame_time / args.fade_time);
+
+            self.strength += (target - self.strength) * rate;
+            self.last = Some((args.camera_rotation, args.player_position));
+        }
+
+        self.strength
+    }
+
+    fn get_cached(&mut self, _frame_time: FrameDelta, _input: Self::Input) -> Self::Output<'_> {
+        self.strength
+    }
+
+    fn reset(&mut self) {
+        self.last = None;
+        self.strength = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use glam::{Quat, Vec3};
+
+    use super::{apply_tracking, scale_roll, swing_twist};
+
+    const EPSILON: f32 = 1e-4;
+
+    fn cameras() -> impl Iterator<Item = Quat> {
+        [-1.55, -0.8, 0.0, 0.6, 1.55].into_iter().flat_map(|pitch| {
+            [0.0, 1.0, -2.5]
+                .into_iter()
+                .map(move |yaw| Quat::from_rotation_y(yaw) * Quat::from_rotation_x(pitch))
+        })
+    }
+
+    fn same(a: Quat, b: Quat) -> bool {
+        // `Quat::angle_between` loses precision close to zero.
+        a.dot(b).abs() > 1.0 - 1e-6
+    }
+
+    fn same_direction(a: Vec3, b: Vec3) -> bool {
+        a.distance(b) < EPSILON
+    }
+
+    #[test]
+    fn identity_tracking_keeps_the_camera() {
+        for camera in cameras() {
+            for (roll_scale, max_pitch) in [(0.0, 0.3), (0.5, FRAC_PI_2), (1.0, 0.0)] {
+                let tracked = apply_tracking(camera, Quat::IDENTITY, roll_scale, max_pitch);
+                assert!(same(tracked, camera));
+            }
+        }
+    }
+
+    #[test]
+    fn zero_roll_scale_removes_tracked_roll() {
+        let tracking =
+            Quat::from_rotation_x(0.2) * Quat::from_rotation_y(-0.3) * Quat::from_rotation_z(0.4);
+
+        for camera in cameras() {
+            let tracked = apply_tracking(camera, tracking, 0.0, FRAC_PI_2);
+
+            let forward = tracked * Vec3::Z;
+            let full_forward = (camera * tracking) * Vec3::Z;
+
+            assert!(same_direction(forward, full_forward));
+            assert!(same(
+                swing_twist(camera.inverse() * tracked, Vec3::Z).1,
+                Quat::IDENTITY
+            ));
+
+            let roll_only = apply_tracking(camera, Quat::from_rotation_z(0.4), 0.0, FRAC_PI_2);
+            assert!(same(roll_only, camera));
+        }
+    }
+
+    #[test]
+    fn pitch_near_vertical_does_not_flip() {
+        for pitch in [-FRAC_PI_2 + 0.01, FRAC_PI_2 - 0.01] {
+            let camera = Quat::from_rotation_y(0.7) * Quat::from_rotation_x(pitch);
+
+            // Tracking that crosses vertical.
+            for tracking_pitch in [-0.1, -0.03, 0.03, 0.1] {
+                let tracking = Quat::from_rotation_x(tracking_pitch) * Quat::from_rotation_z(0.2);
+                let tracked = apply_tracking(camera, tracking, 0.5, FRAC_PI_2);
+
+                let forward = tracked * Vec3::Z;
+                let full_forward = (camera * tracking) * Vec3::Z;
+
+                assert!(same_direction(forward, full_forward));
+                assert!(tracked.angle_between(camera) < 0.15);
+            }
+
+            // The tracked pitch is limited relative to the camera.
+            let tracking = Quat::from_rotation_x(0.5);
+            let tracked = apply_tracking(camera, tracking, 1.0, 0.1);
+
+            let angle = (tracked * Vec3::Z).angle_between(camera * Vec3::Z);
+            assert!((angle - 0.1).abs() < EPSILON);
+        }
+    }
+
+    #[test]
+    fn scale_roll_levels_the_horizon() {
+        let rotation =
+            Quat::from_rotation_y(1.0) * Quat::from_rotation_x(0.3) * Quat::from_rotation_z(0.4);
+
+        let leveled = scale_roll(rotation, 0.0);
+        assert!((leveled * Vec3::X).y.abs() < EPSILON);
+        assert!(same_direction(leveled * Vec3::Z, rotation * Vec3::Z));
+
+        assert!(same(scale_roll(rotation, 1.0), rotation));
+
+        let half = scale_roll(rotation, 0.5);
+        assert!((half.angle_between(rotation) - 0.2).abs() < 0.01);
+    }
+
+    #[test]
+    fn scale_roll_is_continuous_near_vertical() {
+        for pitch in [-FRAC_PI_2, -FRAC_PI_2 + 0.001, FRAC_PI_2 - 0.001, FRAC_PI_2] {
+            let rotation = Quat::from_rotation_x(pitch) * Quat::from_rotation_z(0.3);
+            let scaled = scale_roll(rotation, 0.0);
+
+            assert!(scaled.angle_between(rotation) < 0.01);
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use std::ptr;
+
+use eldenring::cs::ChrIns;
+use fromsoftware_shared::F32ViewMatrix;
+use glam::{Vec2, Vec3, Vec4};
+
+use crate::{
+    core::{BehaviorState, CoreLogicContext, world::World},
+    player::PlayerExt,
+    raycast::{WORLD_FILTER, cast_sphere},
+    shaders::{screen::world_to_screen, set_crosshair_offset, set_crosshair_opacity},
+};
+
+impl CoreLogicContext<'_, World<'_>> {
+    /// Moves the crosshair to where a projectile fired from the player's hand would land.
+    pub fn update_crosshair_offset(&self, camera_pos: F32ViewMatrix) {
+        const MAX_DISTANCE: f32 = 50.0;
+        const PROJECTILE_RADIUS: f32 = 0.05;
+
+        let hand = self
+            .player
+            .ranged_weapon_hand()
+            .filter(|_| self.config.convergent_crosshair);
+
+        let Some(hand) = hand else {
+            set_crosshair_offset(Vec2::ZERO);
+            return;
+        };
+
+        let origin = self.player.hand_matrix(hand).translation();
+        let forward = Vec4::from(camera_pos.2).truncate();
+
+        let player = &self.player.chr_ins as *const ChrIns;
+
+        let hit = cast_sphere(
+            origin,
+            forward * MAX_DISTANCE,
+            PROJECTILE_RADIUS,
+            WORLD_FILTER,
+            |hit| {
+                hit.field_ins()
+                    .is_none_or(|owner| !ptr::eq(owner.as_ptr() as *const ChrIns, player))
+            },
+        );
+
+        let hit_point = hit.map_or(origin + forward * MAX_DISTANCE, |hit| Vec3::from(hit.pos));
+
+        let offset =
+            world_to_screen(camera_pos, self.fov(), hit_point).map_or(Vec2::ZERO, |xy| xy - 0.5);
+
+        set_crosshair_offset(offset);
+    }
+
+    /// Fades the crosshair in while attacking, aiming or casting when idle crosshairs are hidden.
+    pub fn update_crosshair_opacity(&mut self) {
+        let config = self.config;
+
+        if !config.hide_idle_crosshair {
+            self.crosshair_opacity = 1.0;
+            set_crosshair_opacity(1.0);
+            return;
+        }
+
+        let is_engaged = self.has_state(BehaviorState::Attack) || self.is_aiming();
+
+        let target = if is_engaged { 1.0 } else { 0.0 };
+
+        let frame_time = self.frame_time.get(()).real;
+        let step = if config.crosshair_fade_time > 0.0 {
+            frame_time / config.crosshair_fade_time
+        } else {
+            1.0
+        };
+
+        let opacity = self.crosshair_opacity;
+        self.crosshair_opacity = opacity + (target - opacity).clamp(-step, step);
+
+        set_crosshair_opacity(self.crosshair_opacity);
+    }
+}
@@ -0,0 +1,164 @@
+use crate::config::EasingCurve;
+
+/// Moves a distance toward 0 over time following an [`EasingCurve`].
+#[derive(Default)]
+pub struct Easing {
+    velocity: f32,
+}
+
+impl Easing {
+    /// Computes a signed distance step that moves `distance` toward 0 over the next `timedelta`.
+    ///
+    /// The step is clamped to never overshoot: it is always between 0 and `distance`.
+    pub fn step(&mut self, curve: EasingCurve, distance: f32, timedelta: f32) -> f32 {
+        let sign = distance.signum();
+        let distance = distance.abs();
+
+        let step = match curve {
+            EasingCurve::Power { exponent, scale } => {
+                self.velocity = 0.0;
+                rip(distance, 0.0, exponent, scale, timedelta)
+            }
+            EasingCurve::Exponential { time_constant } => {
+                self.velocity = 0.0;
+                distance * (1.0 - f32::exp(-timedelta / time_constant))
+            }
+            EasingCurve::Spring { omega } => self.spring(distance, omega, timedelta),
+        };
+
+        step.max(0.0).min(distance) * sign
+    }
+
+    /**
+        Critically damped spring with angular frequency `omega` (ω).
+
+        Remaining distance: d(t) = (d + (v + ωd)t)e^(-ωt)
+                  Velocity: v(t) = (v - ω(v + ωd)t)e^(-ωt)
+
+        The velocity is kept between steps, so a moving target is followed smoothly.
+    */
+    fn spring(&mut self, distance: f32, omega: f32, timedelta: f32) -> f32 {
+        let v = self.velocity;
+        let c = v + omega * distance;
+        let decay = f32::exp(-omega * timedelta);
+
+        let distance_new = (distance + c * timedelta) * decay;
+
+        if distance_new > 0.0 {
+            self.velocity = (v - omega * c * timedelta) * decay;
+        } else {
+            self.velocity = 0.0;
+        }
+
+        distance - distance_new
+    }
+}
+
+/**
+    Computes a distance step that moves `distance` toward 0 over the next `timedelta`.
+
+      Curve: d(t) = (t * b)^p - a
+    Inverse: t(d) = (d + a)^(1/p) / b
+       Step:        d(t) - d(t-Δt)
+
+    Method:
+    - Interpret `distance` as the remaining distance to zero, offset by `curve_offset`.
+    - Convert remaining distance -> remaining time using t(d), scaled by `curve_scale`.
+    - Advance time by `timedelta` and map back using d(t) to get the new remaining distance.
+    - Return step = distance - distance_new.
+*/
+fn rip(distance: f32, curve_offset: f32, exponent: f32, curve_scale: f32, timedelta: f32) -> f32 {
+    let time_remaining = (distance + curve_offset).powf(exponent.recip()) / curve_scale;
+    let time_new = (time_remaining - timedelta).max(0.0);
+
+    let distance_new = (time_new * curve_scale).powf(exponent) - curve_offset;
+
+    distance - distance_new
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME_TIME: f32 = 1.0 / 60.0;
+
+    fn curves(settle_time: f32) -> [EasingCurve; 4] {
+        [
+            EasingCurve::power(6.0, settle_time),
+            EasingCurve::power(1.0, settle_time),
+            EasingCurve::exponential(settle_time),
+            EasingCurve::spring(settle_time),
+        ]
+    }
+
+    /// Returns the time it takes to settle within `EasingCurve::SETTLE_DISTANCE`
+    /// of the target, checking that the curve never overshoots.
+    fn settle(curve: EasingCurve, distance: f32, max_time: f32) -> f32 {
+        let mut easing = Easing::default();
+        let mut distance = distance;
+        let mut time = 0.0;
+
+        while distance > EasingCurve::SETTLE_DISTANCE && time < max_time {
+            let step = easing.step(curve, distance, FRAME_TIME);
+            assert!(step >= 0.0, "{curve:?} moved away from the target");
+
+            let distance_new = distance - step;
+            assert!(distance_new >= 0.0, "{curve:?} overshot the target");
+            assert!(distance_new <= distance);
+
+            distance = distance_new;
+            time += FRAME_TIME;
+        }
+
+        time
+    }
+
+    #[test]
+    fn convergence_time() {
+        for settle_time in [0.1, 0.5, 1.0, 2.0] {
+            for curve in curves(settle_time) {
+                let time = settle(curve, 1.0, 10.0);
+                assert!(
+                    (time - settle_time).abs() <= FRAME_TIME * 2.0,
+                    "{curve:?} settled in {time}s, expected {settle_time}s"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn no_overshoot() {
+        for curve in curves(0.5) {
+            for distance in [0.001, 0.1, 1.0, 3.0] {
+                settle(curve, distance, 10.0);
+            }
+
+            // Large time steps (e.g. lag spikes) must not overshoot either.
+            let mut easing = Easing::default();
+            assert!(easing.step(curve, 1.0, 5.0) <= 1.0);
+            assert!(easing.step(curve, -1.0, 5.0) >= -1.0);
+        }
+    }
+
+    #[test]
+    fn spring_follows_moving_target() {
+        let curve = EasingCurve::spring(0.5);
+
+        let mut easing = Easing::default();
+        let mut distance = 0.0f32;
+
+        for _ in 0..120 {
+            // The target keeps moving away, the spring must never pass it.
+            distance += 0.02;
+            distance -= easing.step(curve, distance, FRAME_TIME);
+            assert!(distance >= 0.0);
+        }
+    }
+
+    #[test]
+    fn paused() {
+        for curve in curves(1.0) {
+            assert_eq!(Easing::default().step(curve, 1.0, 0.0), 0.0);
+        }
+    }
+}
@@ -1,10 +1,10 @@
 use std::ops::{Deref, DerefMut};
 
-use crate::core::time::{FRAME_TIME_60, FrameTime};
+use crate::core::time::{FrameDelta, FrameTime};
 
 pub struct FrameCached<T: FrameCache> {
     state: State,
-    frame_time: f32,
+    frame_time: FrameDelta,
     cache: T,
 }
 
@@ -27,9 +27,9 @@ pub trait FrameCache {
     where
         Self: 'a;
 
-    fn update(&mut self, frame_time: f32, input: Self::Input) -> Self::Output<'_>;
+    fn update(&mut self, frame_time: FrameDelta, input: Self::Input) -> Self::Output<'_>;
 
-    fn get_cached(&mut self, frame_time: f32, input: Self::Input) -> Self::Output<'_>;
+    fn get_cached(&mut self, frame_time: FrameDelta, input: Self::Input) -> Self::Output<'_>;
 
     fn reset(&mut self);
 }
@@ -38,12 +38,12 @@ impl<T: FrameCache> FrameCached<T> {
     pub const fn new(cache: T) -> Self {
         Self {
             state: State::NOT_UPDATED,
-            frame_time: FRAME_TIME_60,
+            frame_time: FrameDelta::FRAME_60,
             cache,
         }
     }
 
-    pub fn next_frame(&mut self, frame_time: f32) {
+    pub fn next_frame(&mut self, frame_time: FrameDelta) {
         if self.state == State::STALE {
             self.cache.reset();
         }
@@ -64,8 +64,8 @@ impl<T: FrameCache> FrameCached<T> {
 }
 
 impl FrameCached<FrameTime> {
-    pub fn measure(&mut self) -> f32 {
-        self.next_frame(FRAME_TIME_60);
+    pub fn measure(&mut self) -> FrameDelta {
+        self.next_frame(FrameDelta::FRAME_60);
         self.frame_time = self.get(());
         self.frame_time
     }
@@ -2,9 +2,10 @@ use fromsoftware_shared::F32ModelMatrix;
 use glam::{Mat4, Quat, Vec3};
 
 use crate::{
+    config::EasingCurve,
     core::{
-        BehaviorState, CoreLogicContext, frame_cached::FrameCache, stabilizer::CameraStabilizer,
-        world::World,
+        BehaviorState, CoreLogicContext, easing::Easing, frame_cached::FrameCache,
+        stabilizer::CameraStabilizer, time::FrameDelta, world::World,
     },
     player::PlayerExt,
 };
@@ -14,6 +15,8 @@ pub struct HeadTracker {
     last: Option<Quat>,
     rotation: Quat,
     rotation_target: Quat,
+    curve: EasingCurve,
+    easing: Easing,
     stabilizer: CameraStabilizer,
     output: Option<Output>,
 }
@@ -23,7 +26,7 @@ pub struct Args {
     pub head_matrix: F32ModelMatrix,
     pub stabilizer_factor: f32,
     pub use_stabilizer: bool,
-    pub is_tracked: bool,
+    pub tracking_curve: Option<EasingCurve>,
 }
 
 pub struct Output {
@@ -39,7 +42,7 @@ impl HeadTracker {
 
     fn rotate_towards_target(&mut self, frame_time: f32) {
         let distance = self.rotation.angle_between(self.rotation_target);
-        let step = rip(distance, 0.0, 1.0, frame_time);
+        let step = self.easing.step(self.curve, distance, frame_time);
 
         self.rotation = self.rotation.rotate_towards(self.rotation_target, step);
     }
@@ -49,7 +52,7 @@ impl FrameCache for HeadTracker {
     type Input = Args;
     type Output<'a> = &'a Output;
 
-    fn update(&mut self, frame_time: f32, args: Self::Input) -> Self::Output<'_> {
+    fn update(&mut self, frame_time: FrameDelta, args: Self::Input) -> Self::Output<'_> {
         let mut head_position = args.head_matrix.translation();
 
         if args.use_stabilizer {
@@ -67,7 +70,12 @@ impl FrameCache for HeadTracker {
 
         let input = Quat::from_mat3a(&args.head_matrix.rotation());
 
-        if args.is_tracked
+        // Keep using the last tracking curve to return to the camera's rotation.
+        if let Some(curve) = args.tracking_curve {
+            self.curve = curve;
+        }
+
+        if args.tracking_curve.is_some()
             && let Some(last) = self.last
         {
             self.rotation_target *= last.inverse() * input;
@@ -77,7 +85,7 @@ impl FrameCache for HeadTracker {
         }
 
         self.last = Some(input);
-        self.rotate_towards_target(frame_time);
+        self.rotate_towards_target(frame_time.game);
 
         self.output.insert(Output {
             tracking_rotation: self.rotation,
@@ -86,7 +94,7 @@ impl FrameCache for HeadTracker {
         })
     }
 
-    fn get_cached(&mut self, _frame_time: f32, _input: Self::Input) -> Self::Output<'_> {
+    fn get_cached(&mut self, _frame_time: FrameDelta, _input: Self::Input) -> Self::Output<'_> {
         self.output.as_ref().expect("FrameCache logic error")
     }
 
@@ -101,43 +109,24 @@ impl From<&CoreLogicContext<'_, World<'_>>> for Args {
         let head_matrix = context.player.head_matrix();
         let model_matrix = context.player.model_matrix();
 
-        let is_tracked = context.player.is_in_throw()
-            || (context.config.track_damage && context.has_state(BehaviorState::Damage))
-            || (context.config.track_dodges && context.has_state(BehaviorState::Evasion));
+        let config = context.config;
+
+        let tracking_curve = if context.player.is_in_throw() {
+            Some(config.throw_tracking)
+        } else if config.track_damage && context.has_state(BehaviorState::Damage) {
+            Some(config.damage_tracking)
+        } else if config.track_dodges && context.has_state(BehaviorState::Evasion) {
+            Some(config.dodge_tracking)
+        } else {
+            None
+        };
 
         Self {
             head_matrix,
             model_matrix,
-            stabilizer_factor: context.config.stabilizer_factor,
-            use_stabilizer: context.config.use_stabilizer,
-            is_tracked,
+            stabilizer_factor: config.stabilizer_factor,
+            use_stabilizer: config.use_stabilizer,
+            tracking_curve,
         }
     }
 }
-
-/**
-    Computes a signed distance step that moves `distance` toward 0 over the next `timedelta`.
-
-      Curve: d(t) = (t * b)^6 - a
-    Inverse: t(d) = (d + a)^(1/6) / b
-       Step:        d(t) - d(t-Δt)
-
-    Method:
-    - Interpret `distance` as the remaining distance to zero, offset by `curve_offset`.
-    - Convert remaining distance -> remaining time using t(d), scaled by `curve_scale`.
-    - Advance time by `timedelta` and map back using d(t) to get the new remaining distance.
-    - Return step = distance - distance_new, clamped to \[0, distance\].
-*/
-fn rip(distance: f32, curve_offset: f32, curve_scale: f32, timedelta: f32) -> f32 {
-    let sign = distance.signum();
-    let distance = distance.abs();
-
-    let time_remaining = (distance + curve_offset).powf(1.0 / 6.0) / curve_scale;
-    let time_new = (time_remaining - timedelta).max(0.0);
-
-    let distance_new = (time_new * curve_scale).powi(6) - curve_offset;
-
-    let step = (distance - distance_new).max(0.0).min(distance);
-
-    step * sign
-}
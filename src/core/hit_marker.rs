@@ -0,0 +1,248 @@
+use eldenring::cs::FieldInsHandle;
+use fromsoftware_shared::F32ViewMatrix;
+use glam::Vec4;
+
+use crate::{
+    core::{BehaviorState, CoreLogicContext, world::World},
+    player::PlayerExt,
+    shaders::set_hit_marker,
+};
+
+/// Detects hits on recently targeted characters from changes in their HP.
+///
+/// Without access to the game's damage events, only HP drops during or shortly after
+/// the player's own attacks count, which excludes most damage from other sources.
+pub struct HitMarker<H = FieldInsHandle> {
+    targets: Vec<TrackedTarget<H>>,
+    time_since_attack: Option<f32>,
+    time_since_hit: Option<f32>,
+}
+
+struct TrackedTarget<H> {
+    handle: H,
+    hp: i32,
+    age: f32,
+}
+
+/// The state of a lock on target in the current frame.
+pub struct TargetSample<H> {
+    pub handle: H,
+    pub hp: i32,
+    pub is_targeted: bool,
+}
+
+impl HitMarker {
+    // Targets stay tracked for this long after leaving the crosshair.
+    const RECENT_TIME: f32 = 2.0;
+
+    // HP changes this long after a melee attack ends still count as hits.
+    const MELEE_WINDOW: f32 = 0.3;
+
+    // HP changes this long after a ranged attack ends still count as hits, for projectiles in flight.
+    const RANGED_WINDOW: f32 = 1.0;
+
+    // Targets within this angle (in radians) of the crosshair are tracked.
+    const TARGET_CONE_ANGLE: f32 = 0.35;
+}
+
+impl<H> Default for HitMarker<H> {
+    fn default() -> Self {
+        Self {
+            targets: vec![],
+            time_since_attack: None,
+            time_since_hit: None,
+        }
+    }
+}
+
+impl<H: Copy + PartialEq> HitMarker<H> {
+    /// Tracks the HP of targeted characters and checks if any of them dropped
+    /// while the player is attacking, or within `attack_window` seconds after.
+    fn detect_hit(
+        &mut self,
+        is_attacking: bool,
+        attack_window: f32,
+        frame_time: f32,
+        samples: impl IntoIterator<Item = TargetSample<H>>,
+    ) -> bool {
+        self.time_since_attack = if is_attacking {
+            Some(0.0)
+        } else {
+            self.time_since_attack
+                .map(|time| time + frame_time)
+                .filter(|time| *time < attack_window)
+        };
+
+        let mut is_hit = false;
+
+        for sample in samples {
+            match self
+                .targets
+                .iter_mut()
+                .find(|tracked| tracked.handle == sample.handle)
+            {
+                Some(tracked) => {
+                    is_hit |= sample.hp < tracked.hp && self.time_since_attack.is_some();
+
+                    tracked.hp = sample.hp;
+
+                    if sample.is_targeted {
+                        tracked.age = 0.0;
+                    }
+                }
+                None if sample.is_targeted => self.targets.push(TrackedTarget {
+                    handle: sample.handle,
+                    hp: sample.hp,
+                    age: 0.0,
+                }),
+                None => {}
+            }
+        }
+
+        self.targets.retain_mut(|tracked| {
+            tracked.age += frame_time;
+            tracked.age < HitMarker::RECENT_TIME
+        });
+
+        is_hit
+    }
+}
+
+impl CoreLogicContext<'_, World<'_>> {
+    pub fn update_hit_marker(&mut self, camera_pos: F32ViewMatrix) {
+        let config = self.config;
+
+        if !config.use_hit_marker {
+            self.hit_marker.targets.clear();
+            set_hit_marker(None, 0.0, config.hit_marker_color);
+            return;
+        }
+
+        let frame_time = self.frame_time.get(());
+
+        let origin = Vec4::from(camera_pos.3).truncate();
+        let forward = Vec4::from(camera_pos.2).truncate();
+
+        let samples = self
+            .lock_targets()
+            .into_iter()
+            .filter_map(|target| {
+                let is_targeted = target.is_locked
+                    || forward.angle_between(target.center() - origin)
+                        < HitMarker::TARGET_CONE_ANGLE;
+
+                let hp = target.chr()?.module_container.data.hp;
+
+                Some(TargetSample {
+                    handle: target.handle,
+                    hp,
+                    is_targeted,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let is_attacking = self.has_state(BehaviorState::Attack);
+        let attack_window = if self.player.has_ranged_weapon() {
+            HitMarker::RANGED_WINDOW
+        } else {
+            HitMarker::MELEE_WINDOW
+        };
+
+        let hit_marker = &mut self.hit_marker;
+        let is_hit = hit_marker.detect_hit(is_attacking, attack_window, frame_time.game, samples);
+
+        if is_hit {
+            hit_marker.time_since_hit = Some(0.0);
+        } else if let Some(time_since_hit) = &mut hit_marker.time_since_hit {
+            *time_since_hit += frame_time.real;
+        }
+
+        let progress = hit_marker
+            .time_since_hit
+            .map(|time| time / config.hit_marker_duration)
+            .filter(|progress| *progress < 1.0);
+
+        if progress.is_none() {
+            hit_marker.time_since_hit = None;
+        }
+
+        set_hit_marker(
+            progress.map(|_| config.hit_marker_style),
+            progress.unwrap_or(0.0),
+            config.hit_marker_color,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HitMarker, TargetSample};
+
+    const FRAME_TIME: f32 = 1.0 / 60.0;
+    const WINDOW: f32 = 0.3;
+
+    fn sample(handle: u32, hp: i32, is_targeted: bool) -> TargetSample<u32> {
+        TargetSample {
+            handle,
+            hp,
+            is_targeted,
+        }
+    }
+
+    #[test]
+    fn hp_drops_during_attacks_are_hits() {
+        let mut hit_marker = HitMarker::default();
+
+        assert!(!hit_marker.detect_hit(false, WINDOW, FRAME_TIME, [sample(1, 100, true)]));
+        assert!(!hit_marker.detect_hit(true, WINDOW, FRAME_TIME, [sample(1, 100, true)]));
+        assert!(hit_marker.detect_hit(true, WINDOW, FRAME_TIME, [sample(1, 80, true)]));
+
+        // Healing is not a hit.
+        assert!(!hit_marker.detect_hit(true, WINDOW, FRAME_TIME, [sample(1, 90, true)]));
+    }
+
+    #[test]
+    fn hp_drops_shortly_after_attacks_are_hits() {
+        let mut hit_marker = HitMarker::default();
+
+        hit_marker.detect_hit(true, WINDOW, FRAME_TIME, [sample(1, 100, true)]);
+
+        for _ in 0..10 {
+            hit_marker.detect_hit(false, WINDOW, FRAME_TIME, [sample(1, 100, true)]);
+        }
+
+        assert!(hit_marker.detect_hit(false, WINDOW, FRAME_TIME, [sample(1, 80, true)]));
+    }
+
+    #[test]
+    fn hp_drops_without_attacks_are_not_hits() {
+        let mut hit_marker = HitMarker::default();
+
+        hit_marker.detect_hit(true, WINDOW, FRAME_TIME, [sample(1, 100, true)]);
+
+        // e.g. bleed, fall damage or other players after the attack window.
+        for _ in 0..30 {
+            hit_marker.detect_hit(false, WINDOW, FRAME_TIME, [sample(1, 100, true)]);
+        }
+
+        assert!(!hit_marker.detect_hit(false, WINDOW, FRAME_TIME, [sample(1, 80, true)]));
+    }
+
+    #[test]
+    fn untracked_targets_are_not_hits() {
+        let mut hit_marker = HitMarker::default();
+
+        hit_marker.detect_hit(true, WINDOW, FRAME_TIME, [sample(1, 100, false)]);
+        assert!(!hit_marker.detect_hit(true, WINDOW, FRAME_TIME, [sample(1, 80, false)]));
+
+        // Targets stay tracked for a while after leaving the crosshair.
+        hit_marker.detect_hit(true, WINDOW, FRAME_TIME, [sample(2, 100, true)]);
+        assert!(hit_marker.detect_hit(true, WINDOW, FRAME_TIME, [sample(2, 80, false)]));
+
+        for _ in 0..(HitMarker::RECENT_TIME / FRAME_TIME) as usize {
+            hit_marker.detect_hit(true, WINDOW, FRAME_TIME, [sample(2, 80, false)]);
+        }
+
+        assert!(!hit_marker.detect_hit(true, WINDOW, FRAME_TIME, [sample(2, 60, false)]));
+    }
+}
@@ -0,0 +1,289 @@
+use eldenring::cs::{ChrIns, FieldInsHandle, WorldChrMan};
+use fromsoftware_shared::{F32ViewMatrix, FromStatic};
+use glam::{Quat, Vec2, Vec3, Vec4};
+
+use crate::{
+    config::AimPoint,
+    core::{CoreLogicContext, world::World},
+    input::take_flick_direction,
+    player::PlayerExt,
+    raycast::has_line_of_sight,
+    shaders::screen::{correct_screen_coords, to_square_screen, world_to_screen},
+};
+
+/// A character in the lock on target list of `LockTgtMan`.
+///
+/// Only the handle is kept, as the character may be unloaded at any time.
+pub struct LockTarget {
+    pub handle: FieldInsHandle,
+    pub position: Vec3,
+    pub is_locked: bool,
+}
+
+impl LockTarget {
+    // Approximate height of the center of a human sized character.
+    const CENTER_HEIGHT: f32 = 1.0;
+
+    // The lock on position is usually placed at about this fraction of a character's height.
+    const LOCK_HEIGHT_RATIO: f32 = 0.7;
+
+    /// Looks up the character by its handle, if it is still loaded.
+    pub fn chr(&self) -> Option<&ChrIns> {
+        let world_chr_man = unsafe { WorldChrMan::instance().ok()? };
+        let chr: &ChrIns = world_chr_man.chr_ins_by_handle(&self.handle)?;
+        Some(chr)
+    }
+
+    pub fn center(&self) -> Vec3 {
+        self.position + Vec3::Y * Self::CENTER_HEIGHT
+    }
+
+    /// Estimates a point on the character from its lock on position.
+    pub fn aim_point(&self, lock_on_pos: Vec3, aim_point: AimPoint) -> Vec3 {
+        let height = (lock_on_pos.y - self.position.y).max(0.0) / Self::LOCK_HEIGHT_RATIO;
+
+        let height_ratio = match aim_point {
+            AimPoint::Lock => return lock_on_pos,
+            AimPoint::Head => 0.92,
+            AimPoint::Chest => 0.72,
+            AimPoint::Center => 0.55,
+        };
+
+        Vec3::new(
+            lock_on_pos.x,
+            self.position.y + height * height_ratio,
+            lock_on_pos.z,
+        )
+    }
+}
+
+impl CoreLogicContext<'_, World<'_>> {
+    pub fn lock_targets(&self) -> Vec<LockTarget> {
+        let Ok(world_chr_man) = (unsafe { WorldChrMan::instance() }) else {
+            return vec![];
+        };
+
+        let mut targets = vec![];
+        let mut next_node = self.lock_tgt.nodes;
+
+        while let Some(node) = next_node.map(|ptr| unsafe { ptr.as_ref() }) {
+            next_node = node.next;
+
+            let handle = unsafe { node.value.as_ref().chr_handle };
+
+            if let Some(chr) = world_chr_man.chr_ins_by_handle(&handle) {
+                targets.push(LockTarget {
+                    handle,
+                    position: Vec4::from(chr.chr_ctrl.model_matrix.3).truncate(),
+                    is_locked: node.flags & 32 != 0,
+                });
+            }
+        }
+
+        targets
+    }
+
+    pub fn locked_target(&self) -> Option<LockTarget> {
+        self.lock_targets()
+            .into_iter()
+            .find(|target| target.is_locked)
+    }
+
+    /// Locks on to the best scoring target in a cone around the crosshair.
+    pub fn soft_lock_on(&mut self, camera_pos: F32ViewMatrix) {
+        self.lock_tgt.lock_camera = false;
+
+        if !self.lock_tgt.is_lock_on_requested {
+            return;
+        }
+
+        let origin = Vec4::from(camera_pos.3).truncate();
+        let forward = Vec4::from(camera_pos.2).truncate();
+
+        let lock_on = &self.config.lock_on;
+
+        let best_target = self
+            .lock_targets()
+            .into_iter()
+            .filter_map(|target| {
+                let to_target = target.center() - origin;
+
+                let distance = to_target.length();
+                let angle = forward.angle_between(to_target);
+
+                if distance > lock_on.soft_max_distance
+                    || angle > lock_on.soft_cone_angle
+                    || !target.chr().is_some_and(|chr| self.player.can_target(chr))
+                {
+                    return None;
+                }
+
+                let is_occluded = !has_line_of_sight(origin, target.center());
+
+                let score = angle / lock_on.soft_cone_angle * lock_on.angle_weight
+                    + distance / lock_on.soft_max_distance * lock_on.distance_weight
+                    + if is_occluded {
+                        lock_on.occlusion_weight
+                    } else {
+                        0.0
+                    };
+
+                Some((target.handle, score))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b));
+
+        if let Some((handle, _)) = best_target {
+            self.lock_on_to(handle);
+        }
+    }
+
+    /// Replaces a vanilla lock on target switch with the closest visible target
+    /// in the direction of the flick on screen.
+    ///
+    /// The vanilla switch picks targets relative to the third person camera, so the
+    /// direction is taken from the right stick or mouse flick that caused it. Switches
+    /// without a recent flick, e.g. after the target dies, are left to the game.
+    /// Without a visible target in that direction, the one closest to the crosshair is picked.
+    pub fn switch_lock_target(&mut self, previous: FieldInsHandle) {
+        // Penalty for targets away from the line of the flick.
+        const ACROSS_WEIGHT: f32 = 2.0;
+
+        let camera = self.chr_cam.pers_cam.matrix;
+        let fov = self.chr_cam.pers_cam.fov;
+
+        let origin = Vec4::from(camera.3).truncate();
+        let project = |target: &LockTarget| {
+            world_to_screen(camera, fov, target.center()).map(to_square_screen)
+        };
+
+        let targets = self.lock_targets();
+
+        let Some(current) = targets.iter().find(|target| target.is_locked) else {
+            return;
+        };
+
+        if current.handle == previous {
+            return;
+        }
+
+        let Some(direction) = take_flick_direction() else {
+            return;
+        };
+
+        let previous_target = targets.iter().find(|target| {
+            target.handle == previous && target.chr().is_some_and(|chr| self.player.can_target(chr))
+        });
+
+        let center = to_square_screen(correct_screen_coords(Vec2::splat(0.5)));
+        let from = previous_target.and_then(project).unwrap_or(center);
+
+        let candidates = targets
+            .iter()
+            .filter(|target| {
+                target.handle != previous
+                    && target.chr().is_some_and(|chr| self.player.can_target(chr))
+            })
+            .filter_map(|target| Some((target, project(target)?)))
+            .filter(|(target, _)| has_line_of_sight(origin, target.center()))
+            .collect::<Vec<_>>();
+
+        let best_in_direction = candidates
+            .iter()
+            .filter_map(|(target, position)| {
+                let offset = position - from;
+
+                let along = offset.dot(direction);
+                let across = offset.perp_dot(direction).abs();
+
+                (along > 0.0).then_some((target.handle, along + across * ACROSS_WEIGHT))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b));
+
+        let best_target = best_in_direction.or_else(|| {
+            candidates
+                .iter()
+                .map(|(target, position)| (target.handle, position.distance(center)))
+                .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        });
+
+        match best_target {
+            Some((handle, _)) if handle != current.handle => self.lock_on_to(handle),
+            Some(_) => {}
+            // Nothing else is visible, keep the previous target if it can still be targeted.
+            None => {
+                if let Some(previous) = previous_target {
+                    self.lock_on_to(previous.handle);
+                }
+            }
+        }
+    }
+
+    /// Drops a hard lock once the target has been out of view or occluded
+    /// for `lock_on.release_time` seconds.
+    pub fn update_lock_release(&mut self) {
+        let config = self.config;
+
+        if !config.lock_on.auto_release
+            || config.soft_lock_on
+            || !self.first_person()
+            || !self.lock_tgt.is_locked_on
+        {
+            self.lock_lost_time = 0.0;
+            return;
+        }
+
+        let Some(target) = self.locked_target() else {
+            self.lock_lost_time = 0.0;
+            return;
+        };
+
+        let camera = self.chr_cam.pers_cam.matrix;
+        let fov = self.chr_cam.pers_cam.fov;
+
+        let origin = Vec4::from(camera.3).truncate();
+        let is_visible = world_to_screen(camera, fov, target.center()).is_some()
+            && has_line_of_sight(origin, target.center());
+
+        if is_visible {
+            self.lock_lost_time = 0.0;
+            return;
+        }
+
+        self.lock_lost_time += self.frame_time.get(()).game;
+
+        if self.lock_lost_time >= config.lock_on.release_time {
+            self.lock_lost_time = 0.0;
+            self.lock_tgt.is_lock_on_requested = false;
+        }
+    }
+
+    /// Computes a pitch correction that moves the hard lock on camera from the game's
+    /// lock on position to the configured aim point, as seen from `eye`.
+    pub fn lock_aim_correction(&self, eye: Vec3) -> Quat {
+        let lock_on = &self.config.lock_on;
+
+        if self.config.soft_lock_on
+            || !self.lock_tgt.is_locked_on
+            || lock_on.aim_point == AimPoint::Lock && lock_on.aim_offset == 0.0
+        {
+            return Quat::IDENTITY;
+        }
+
+        let Some(target) = self.locked_target() else {
+            return Quat::IDENTITY;
+        };
+
+        let lock_on_pos = Vec4::from(self.player.lock_on_target_position).truncate();
+        let aim_point =
+            target.aim_point(lock_on_pos, lock_on.aim_point) + Vec3::Y * lock_on.aim_offset;
+
+        match (
+            (lock_on_pos - eye).try_normalize(),
+            (aim_point - eye).try_normalize(),
+        ) {
+            // Both directions only differ vertically, so the arc is a change in pitch.
+            (Some(from), Some(to)) => Quat::from_rotation_arc(from, to),
+            _ => Quat::IDENTITY,
+        }
+    }
+}
@@ -2,7 +2,7 @@ use std::collections::VecDeque;
 
 use glam::Vec3;
 
-use crate::core::frame_cached::FrameCache;
+use crate::core::{frame_cached::FrameCache, time::FrameDelta};
 
 pub struct CameraStabilizer {
     window: f32,
@@ -38,14 +38,18 @@ impl FrameCache for CameraStabilizer {
     type Input = Vec3;
     type Output<'a> = Vec3;
 
-    fn update(&mut self, frame_time: f32, input: Self::Input) -> Self::Output<'_> {
-        self.samples = (self.window / frame_time).ceil() as u32;
-        self.buf.push_front(input);
-        self.buf.truncate(self.samples as usize);
+    fn update(&mut self, frame_time: FrameDelta, input: Self::Input) -> Self::Output<'_> {
+        // Don't sample while the game is paused.
+        if frame_time.game > 0.0 {
+            self.samples = (self.window / frame_time.game).ceil() as u32;
+            self.buf.push_front(input);
+            self.buf.truncate(self.samples as usize);
+        }
+
         self.average(input)
     }
 
-    fn get_cached(&mut self, _frame_time: f32, input: Self::Input) -> Self::Output<'_> {
+    fn get_cached(&mut self, _frame_time: FrameDelta, input: Self::Input) -> Self::Output<'_> {
         self.average(input)
     }
 
@@ -1,11 +1,22 @@
 use std::time::Instant;
 
-use crate::core::frame_cached::{FrameCache};
+use crate::core::frame_cached::FrameCache;
 
 pub const FRAME_TIME_60: f32 = 1.0 / 60.0;
 
+/// Time elapsed since the last frame.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FrameDelta {
+    /// Wall clock time, for anything driven by player input.
+    pub real: f32,
+    /// In-game time, which stops when the game is paused and follows its time scale.
+    /// Use for anything synchronized with animations.
+    pub game: f32,
+}
+
 pub struct FrameTime {
     instant: Option<Instant>,
+    game_time: Option<f32>,
 }
 
 #[derive(Default)]
@@ -13,6 +24,24 @@ pub struct TransTime {
     time: f32,
 }
 
+impl FrameDelta {
+    pub const FRAME_60: Self = Self {
+        real: FRAME_TIME_60,
+        game: FRAME_TIME_60,
+    };
+}
+
+impl FrameTime {
+    // Upper bound for the game time scale, in case the game reports a stale time step.
+    const MAX_TIME_SCALE: f32 = 4.0;
+
+    /// Sets the in-game time step of the current frame, reported by the game
+    /// to `CAMERA_STEP_UPDATE`.
+    pub fn set_game_time(&mut self, time: f32) {
+        self.game_time = Some(time);
+    }
+}
+
 impl TransTime {
     const STATE_TRANS_TIME: f32 = 0.233;
 
@@ -23,27 +52,33 @@ impl TransTime {
 
 impl FrameCache for FrameTime {
     type Input = ();
-    type Output<'a> = f32;
+    type Output<'a> = FrameDelta;
 
-    fn update(&mut self, _frame_time: f32, _input: Self::Input) -> Self::Output<'_> {
+    fn update(&mut self, _frame_time: FrameDelta, _input: Self::Input) -> Self::Output<'_> {
         let now = Instant::now();
 
-        let elapsed = self
+        let real = self
             .instant
             .and_then(|instant| now.checked_duration_since(instant))
             .map_or(FRAME_TIME_60, |dur| dur.as_secs_f32());
 
         self.instant = Some(now);
 
-        elapsed
+        // Fall back to wall clock time when the game has not reported a time step.
+        let game = self.game_time.take().map_or(real, |game| {
+            game.clamp(0.0, real.max(FRAME_TIME_60) * Self::MAX_TIME_SCALE)
+        });
+
+        FrameDelta { real, game }
     }
 
-    fn get_cached(&mut self, frame_time: f32, _input: Self::Input) -> Self::Output<'_> {
+    fn get_cached(&mut self, frame_time: FrameDelta, _input: Self::Input) -> Self::Output<'_> {
         frame_time
     }
 
     fn reset(&mut self) {
         self.instant = None;
+        self.game_time = None;
     }
 }
 
@@ -51,12 +86,12 @@ impl FrameCache for TransTime {
     type Input = ();
     type Output<'a> = f32;
 
-    fn update(&mut self, frame_time: f32, _input: Self::Input) -> Self::Output<'_> {
-        self.time += frame_time;
+    fn update(&mut self, frame_time: FrameDelta, _input: Self::Input) -> Self::Output<'_> {
+        self.time += frame_time.real;
         self.time
     }
 
-    fn get_cached(&mut self, _frame_time: f32, _input: Self::Input) -> Self::Output<'_> {
+    fn get_cached(&mut self, _frame_time: FrameDelta, _input: Self::Input) -> Self::Output<'_> {
         self.time
     }
 
@@ -69,6 +104,7 @@ impl Default for FrameTime {
     fn default() -> Self {
         Self {
             instant: Some(Instant::now()),
+            game_time: None,
         }
     }
 }
@@ -20,7 +20,7 @@ use crate::{
         POSTURE_CONTROL_RIGHT_RVA, SET_WWISE_LISTENER_RVA, UPDATE_CHR_MODEL_POS_RVA,
         UPDATE_FE_MAN_RVA, UPDATE_FOLLOW_CAM_RVA, UPDATE_LOCK_TGT_RVA,
     },
-    shaders::screen::correct_screen_coords,
+    shaders::screen::{HudLayout, ScreenCorrection},
 };
 
 pub mod install;
@@ -32,7 +32,7 @@ pub fn hook_camera(program: Program) -> eyre::Result<()> {
             .derva_ptr::<unsafe extern "C" fn(*mut c_void, *const FD4Time)>(CAMERA_STEP_UPDATE_RVA);
 
         hook(update, |original| {
-            move |param_1, param_2| update_camera(&|| original(param_1, param_2))
+            move |param_1, param_2| update_camera(&*param_2, &|| original(param_1, param_2))
         });
 
         let mms_update =
@@ -162,7 +162,10 @@ unsafe fn update_chr_model_pos(chr_ctrl: *mut ChrCtrl, original: &dyn Fn()) {
 }
 
 #[cfg_attr(debug_assertions, libhotpatch::hotpatch)]
-unsafe fn update_camera(original: &dyn Fn()) {
+unsafe fn update_camera(time: &FD4Time, original: &dyn Fn()) {
+    // The game time step is used for the next `update_move_map_step` call.
+    CoreLogic::scope_mut::<Void, _>(|context| context.set_game_time(time.time));
+
     let camera_updated = CoreLogic::scope_mut::<World, _>(|context| {
         context.update_cs_cam();
         context.first_person()
@@ -188,9 +191,24 @@ unsafe fn update_move_map_step(original: &dyn Fn()) {
 
 #[cfg_attr(debug_assertions, libhotpatch::hotpatch)]
 unsafe fn update_lock_tgt(original: &dyn Fn()) {
+    let locked_target =
+        CoreLogic::scope::<World, _>(|context| context.locked_target().map(|target| target.handle))
+            .flatten();
+
     original();
 
     CoreLogic::scope_mut::<World, _>(|context| {
+        if let Some(previous) = locked_target
+            && context.config.lock_on.flick_switch
+            && !context.config.soft_lock_on
+            && context.first_person()
+            && context.lock_tgt.is_locked_on
+        {
+            context.switch_lock_target(previous);
+        }
+
+        context.update_lock_release();
+
         if context.first_person()
             && !context.can_transition()
             && !context.is_player_sprinting()
@@ -223,13 +241,17 @@ unsafe fn update_fe_man() {
         return;
     };
 
-    const FE_XY: Vec2 = Vec2::new(1920.0, 1080.0);
+    let Some(correction) = ScreenCorrection::current() else {
+        return;
+    };
+
+    let hud_aspect_ratio = CoreLogic::scope::<Void, _>(|context| context.config.hud_aspect_ratio);
+    let layout = HudLayout::current(hud_aspect_ratio);
     let correct_coords = |coords: &mut F32Vector4| {
-        let screen_coords = Vec2::new(coords.0, coords.1);
-        let corrected_screen_coords = correct_screen_coords(screen_coords / FE_XY) * FE_XY;
+        let corrected_coords = layout.correct(&correction, Vec2::new(coords.0, coords.1));
 
-        coords.0 = corrected_screen_coords.x;
-        coords.1 = corrected_screen_coords.y;
+        coords.0 = corrected_coords.x;
+        coords.1 = corrected_coords.y;
     };
 
     correct_coords(&mut fe_man.lock_on_pos);
@@ -0,0 +1,236 @@
+use std::{
+    ffi::c_void,
+    mem,
+    sync::{
+        Mutex,
+        atomic::{AtomicIsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use glam::Vec2;
+use windows::Win32::{
+    Foundation::{HWND, LPARAM, LRESULT, WPARAM},
+    System::Threading::GetCurrentProcessId,
+    UI::{
+        Input::{
+            GetRawInputData, HRAWINPUT,
+            KeyboardAndMouse::MOUSE_MOVE_ABSOLUTE,
+            RAWINPUT, RAWINPUTHEADER, RID_INPUT, RIM_TYPEMOUSE,
+            XboxController::{XINPUT_STATE, XInputGetState, XUSER_MAX_COUNT},
+        },
+        WindowsAndMessaging::{
+            CallWindowProcW, GWLP_WNDPROC, GetForegroundWindow, GetWindowLongPtrW,
+            GetWindowThreadProcessId, SetWindowLongPtrW, WM_INPUT, WNDPROC,
+        },
+    },
+};
+
+/// The state of the first connected XInput gamepad, polled once per frame.
+///
+/// Polling disconnected slots is slow, so the connected slot is cached and
+/// the other slots are only scanned every `RESCAN_INTERVAL`.
+struct Gamepad {
+    slot: Option<u32>,
+    last_scan: Option<Instant>,
+    packet_number: u32,
+    last_active: Option<Instant>,
+}
+
+static GAMEPAD: Mutex<Gamepad> = Mutex::new(Gamepad {
+    slot: None,
+    last_scan: None,
+    packet_number: 0,
+    last_active: None,
+});
+
+/// The direction of the last right stick or mouse flick, and when it happened.
+static LAST_FLICK: Mutex<Option<(Vec2, Instant)>> = Mutex::new(None);
+
+/// Relative mouse movement received by the game window since the last poll.
+static MOUSE_DELTA: Mutex<Vec2> = Mutex::new(Vec2::ZERO);
+
+/// The game window procedure, after the window is hooked by [`hook_game_window`].
+static ORIGINAL_WINDOW_PROC: AtomicIsize = AtomicIsize::new(0);
+
+// Flicks are remembered for a few frames, until the game switches lock on targets.
+const FLICK_TIME: Duration = Duration::from_millis(300);
+
+// The right stick deflection at which a movement counts as a flick.
+const STICK_FLICK_THRESHOLD: f32 = 0.5;
+
+// The mouse movement (in raw counts per frame) at which a movement counts as a flick.
+const MOUSE_FLICK_THRESHOLD: f32 = 30.0;
+
+impl Gamepad {
+    const RESCAN_INTERVAL: Duration = Duration::from_secs(2);
+
+    // A gamepad is considered in use for this long after its last input.
+    const ACTIVE_TIME: Duration = Duration::from_secs(5);
+
+    fn poll(&mut self, now: Instant) {
+        let mut state = self.slot.and_then(get_state);
+
+        if state.is_none() {
+            self.slot = None;
+        }
+
+        if self.slot.is_none()
+            && self
+                .last_scan
+                .is_none_or(|last_scan| now - last_scan >= Self::RESCAN_INTERVAL)
+        {
+            self.last_scan = Some(now);
+            self.slot = (0..XUSER_MAX_COUNT).find(|&slot| {
+                state = get_state(slot);
+                state.is_some()
+            });
+        }
+
+        let Some(state) = state else {
+            return;
+        };
+
+        if state.dwPacketNumber != self.packet_number {
+            self.packet_number = state.dwPacketNumber;
+            self.last_active = Some(now);
+        }
+
+        let right_stick = right_stick(state.Gamepad.sThumbRX, state.Gamepad.sThumbRY);
+
+        if right_stick.length() >= STICK_FLICK_THRESHOLD {
+            record_flick(Vec2::new(right_stick.x, -right_stick.y), now);
+        }
+    }
+}
+
+/// Polls the gamepad and mouse input, called once per frame.
+pub fn poll_input() {
+    let now = Instant::now();
+
+    GAMEPAD.lock().unwrap().poll(now);
+
+    if ORIGINAL_WINDOW_PROC.load(Ordering::Relaxed) == 0 {
+        unsafe { hook_game_window() };
+    }
+
+    let mouse_delta = mem::take(&mut *MOUSE_DELTA.lock().unwrap());
+
+    if mouse_delta.length() >= MOUSE_FLICK_THRESHOLD {
+        record_flick(mouse_delta, now);
+    }
+}
+
+/// Checks if a gamepad is connected and has been used recently.
+pub fn is_gamepad_active() -> bool {
+    let gamepad = GAMEPAD.lock().unwrap();
+
+    gamepad.slot.is_some()
+        && gamepad
+            .last_active
+            .is_some_and(|last_active| last_active.elapsed() < Gamepad::ACTIVE_TIME)
+}
+
+/// Takes the direction of a right stick or mouse flick from the last few frames,
+/// in screen space with y pointing down.
+pub fn take_flick_direction() -> Option<Vec2> {
+    let (direction, time) = LAST_FLICK.lock().unwrap().take()?;
+
+    (time.elapsed() < FLICK_TIME).then(|| direction.normalize())
+}
+
+fn record_flick(direction: Vec2, now: Instant) {
+    *LAST_FLICK.lock().unwrap() = Some((direction, now));
+}
+
+/// Subclasses the game window to observe the raw mouse input it receives.
+///
+/// Raw input is left in place for the game to read. Without raw mouse input,
+/// e.g. before the game window is in the foreground, no mouse flicks are detected.
+unsafe fn hook_game_window() {
+    let window = unsafe { GetForegroundWindow() };
+
+    let mut process_id = 0;
+    unsafe { GetWindowThreadProcessId(window, Some(&mut process_id)) };
+
+    if window.is_invalid() || process_id != unsafe { GetCurrentProcessId() } {
+        return;
+    }
+
+    unsafe {
+        // Stored before hooking, as the window procedure may run on another thread.
+        let original = GetWindowLongPtrW(window, GWLP_WNDPROC);
+        ORIGINAL_WINDOW_PROC.store(original, Ordering::Relaxed);
+
+        SetWindowLongPtrW(window, GWLP_WNDPROC, window_proc as usize as isize);
+    }
+}
+
+unsafe extern "system" fn window_proc(
+    window: HWND,
+    message: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    if message == WM_INPUT
+        && let Some(delta) = unsafe { raw_mouse_delta(lparam) }
+        && let Ok(mut mouse_delta) = MOUSE_DELTA.lock()
+    {
+        *mouse_delta += delta;
+    }
+
+    unsafe {
+        let original =
+            mem::transmute::<isize, WNDPROC>(ORIGINAL_WINDOW_PROC.load(Ordering::Relaxed));
+
+        CallWindowProcW(original, window, message, wparam, lparam)
+    }
+}
+
+/// Reads the relative mouse movement of a `WM_INPUT` message.
+unsafe fn raw_mouse_delta(lparam: LPARAM) -> Option<Vec2> {
+    let mut input = RAWINPUT::default();
+    let mut size = mem::size_of::<RAWINPUT>() as u32;
+
+    let result = unsafe {
+        GetRawInputData(
+            HRAWINPUT(lparam.0 as *mut c_void),
+            RID_INPUT,
+            Some((&raw mut input).cast()),
+            &mut size,
+            mem::size_of::<RAWINPUTHEADER>() as u32,
+        )
+    };
+
+    if result == u32::MAX || input.header.dwType != RIM_TYPEMOUSE.0 {
+        return None;
+    }
+
+    let mouse = unsafe { input.data.mouse };
+
+    (mouse.usFlags.0 & MOUSE_MOVE_ABSOLUTE.0 == 0)
+        .then(|| Vec2::new(mouse.lLastX as f32, mouse.lLastY as f32))
+}
+
+fn get_state(slot: u32) -> Option<XINPUT_STATE> {
+    let mut state = XINPUT_STATE::default();
+
+    (unsafe { XInputGetState(slot, &mut state) } == 0).then_some(state)
+}
+
+/// Maps raw right stick values to `[-1, 1]` with the dead zone removed.
+fn right_stick(x: i16, y: i16) -> Vec2 {
+    // XINPUT_GAMEPAD_RIGHT_THUMB_DEADZONE
+    const RIGHT_THUMB_DEADZONE: f32 = 8689.0;
+
+    let stick = Vec2::new(x as f32, y as f32);
+    let length = stick.length();
+
+    if length <= RIGHT_THUMB_DEADZONE {
+        return Vec2::ZERO;
+    }
+
+    let deflection = (length - RIGHT_THUMB_DEADZONE) / (i16::MAX as f32 - RIGHT_THUMB_DEADZONE);
+
+    stick * (deflection.min(1.0) / length)
+}
@@ -11,6 +11,7 @@ mod config;
 mod core;
 mod game;
 mod hooks;
+mod input;
 mod logger;
 mod player;
 mod program;
@@ -23,6 +23,10 @@ pub trait PlayerExt {
 
     fn head_matrix(&self) -> F32ModelMatrix;
 
+    fn hand_matrix(&self, hand: Hand) -> F32ModelMatrix;
+
+    fn dmy_matrix(&self, dmy_id: u32) -> F32ModelMatrix;
+
     fn location_entity_matrix_mut(&mut self) -> &mut F32ModelMatrix;
 
     fn aim_matrix_mut(&mut self) -> &mut F32ViewMatrix;
@@ -60,6 +64,59 @@ pub trait PlayerExt {
     fn lh_weapon_param(&self) -> Option<(u32, &'static EQUIP_PARAM_WEAPON_ST)>;
 
     fn rh_weapon_param(&self) -> Option<(u32, &'static EQUIP_PARAM_WEAPON_ST)>;
+
+    fn ranged_weapon_hand(&self) -> Option<Hand>;
+
+    fn has_ranged_weapon(&self) -> bool {
+        self.ranged_weapon_hand().is_some()
+    }
+
+    fn weapon_category(&self) -> WeaponCategory;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hand {
+    Left,
+    Right,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WeaponCategory {
+    Fist,
+    Melee,
+    Bow,
+    Crossbow,
+    Ballista,
+    Staff,
+    Seal,
+    Shield,
+    Torch,
+}
+
+impl WeaponCategory {
+    pub fn from_param(row: &EQUIP_PARAM_WEAPON_ST) -> Self {
+        match row.wep_type() {
+            // Fist, claw.
+            35 | 37 => Self::Fist,
+            // Light bow, bow, greatbow.
+            50 | 51 | 53 => Self::Bow,
+            55 => Self::Crossbow,
+            56 => Self::Ballista,
+            57 => Self::Staff,
+            61 => Self::Seal,
+            // Small shield, medium shield, greatshield.
+            65 | 67 | 69 => Self::Shield,
+            87 => Self::Torch,
+            _ => Self::Melee,
+        }
+    }
+
+    pub fn is_ranged(self) -> bool {
+        matches!(
+            self,
+            Self::Bow | Self::Crossbow | Self::Ballista | Self::Staff | Self::Seal
+        )
+    }
 }
 
 impl PlayerExt for PlayerIns {
@@ -75,6 +132,21 @@ impl PlayerExt for PlayerIns {
     }
 
     fn head_matrix(&self) -> F32ModelMatrix {
+        const HEAD_DMY_ID: u32 = 907;
+        self.dmy_matrix(HEAD_DMY_ID)
+    }
+
+    fn hand_matrix(&self, hand: Hand) -> F32ModelMatrix {
+        const LEFT_HAND_DMY_ID: u32 = 20;
+        const RIGHT_HAND_DMY_ID: u32 = 21;
+
+        match hand {
+            Hand::Left => self.dmy_matrix(LEFT_HAND_DMY_ID),
+            Hand::Right => self.dmy_matrix(RIGHT_HAND_DMY_ID),
+        }
+    }
+
+    fn dmy_matrix(&self, dmy_id: u32) -> F32ModelMatrix {
         type GetDmyPos = unsafe extern "C" fn(
             *const ChrIns,
             *mut F32ModelMatrix,
@@ -82,13 +154,12 @@ impl PlayerExt for PlayerIns {
             i32,
         ) -> *mut F32Vector4;
 
-        // Fetch a model matrix for the head dummy poly in world space.
-        const HEAD_DMY_ID: u32 = 907;
+        // Fetch a model matrix for the dummy poly in world space.
         unsafe {
             let get_dmy_pos = Program::current().derva_ptr::<GetDmyPos>(GET_DMY_POS_RVA);
 
             let mut dmy_pos = F32ModelMatrix::IDENTITY;
-            get_dmy_pos(&**self, &mut dmy_pos, &HEAD_DMY_ID, 1);
+            get_dmy_pos(&**self, &mut dmy_pos, &dmy_id, 1);
 
             dmy_pos
         }
@@ -286,6 +357,38 @@ impl PlayerExt for PlayerIns {
         let weapon_param = unsafe { FD4ParamRepository::instance().ok()?.get(weapon_param_id)? };
         Some((weapon_param_id, weapon_param))
     }
+
+    fn weapon_category(&self) -> WeaponCategory {
+        let hand = match self.chr_asm.equipment.arm_style {
+            ChrAsmArmStyle::LeftBothHands => Hand::Left,
+            ChrAsmArmStyle::RightBothHands => Hand::Right,
+            _ => self.ranged_weapon_hand().unwrap_or(Hand::Right),
+        };
+
+        let param = match hand {
+            Hand::Left => self.lh_weapon_param(),
+            Hand::Right => self.rh_weapon_param(),
+        };
+
+        param.map_or(WeaponCategory::Fist, |(_, row)| {
+            WeaponCategory::from_param(row)
+        })
+    }
+
+    fn ranged_weapon_hand(&self) -> Option<Hand> {
+        let is_ranged = |param: Option<(u32, &EQUIP_PARAM_WEAPON_ST)>| {
+            param.is_some_and(|(_, row)| WeaponCategory::from_param(row).is_ranged())
+        };
+
+        let lh_ranged = || is_ranged(self.lh_weapon_param()).then_some(Hand::Left);
+        let rh_ranged = || is_ranged(self.rh_weapon_param()).then_some(Hand::Right);
+
+        match self.chr_asm.equipment.arm_style {
+            ChrAsmArmStyle::LeftBothHands => lh_ranged(),
+            ChrAsmArmStyle::RightBothHands => rh_ranged(),
+            _ => rh_ranged().or_else(lh_ranged),
+        }
+    }
 }
 
 fn enable_parts_visibilty(parts: &mut Option<OwnedPtr<CSModelIns>>, state: bool) {
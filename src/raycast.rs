@@ -6,7 +6,7 @@ use std::{
     ptr::{self, NonNull},
 };
 
-use eldenring::cs::{CSHavokMan, CSPhysIns, FieldInsBase, FieldInsHandle, hknpWorld};
+use eldenring::cs::{CSHavokMan, CSPhysIns, FieldInsBase, FieldInsHandle, FieldInsType, hknpWorld};
 use fromsoftware_shared::FromStatic;
 use glam::{Mat4, Vec3, Vec3A, Vec4};
 
@@ -15,6 +15,9 @@ use crate::{
     rva::{CAM_HIT_COLLECTOR_RVA, CAST_SHAPE_RVA, HKNP_SPHERE_SHAPE_RVA},
 };
 
+// Collision filter for world geometry and characters.
+pub const WORLD_FILTER: u32 = 0x2000058;
+
 pub fn cast_sphere<F>(
     origin: Vec3,
     direction: Vec3,
@@ -28,6 +31,16 @@ where
     cast_shape(origin, direction, filter, hknpSphereShape::new(radius), f)
 }
 
+/// Checks that no world geometry is between `from` and `to`, ignoring characters.
+pub fn has_line_of_sight(from: Vec3, to: Vec3) -> bool {
+    const LINE_OF_SIGHT_RADIUS: f32 = 0.05;
+
+    cast_sphere(from, to - from, LINE_OF_SIGHT_RADIUS, WORLD_FILTER, |hit| {
+        !hit.is_chr()
+    })
+    .is_none()
+}
+
 fn cast_shape<T, F>(
     origin: Vec3,
     direction: Vec3,
@@ -222,6 +235,11 @@ impl hknpHit {
     pub fn field_ins_handle(&self) -> Option<FieldInsHandle> {
         unsafe { Some(self.field_ins()?.as_ref().handle) }
     }
+
+    pub fn is_chr(&self) -> bool {
+        self.field_ins_handle()
+            .is_some_and(|handle| handle.selector.field_ins_type() == Some(FieldInsType::Chr))
+    }
 }
 
 impl<'a> CustomHitCollector<'a> {
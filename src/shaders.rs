@@ -1,45 +1,65 @@
 use std::{
     arch::naked_asm,
     ffi::c_void,
-    sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, AtomicU32, Ordering},
+    },
 };
 
+use glam::Vec2;
 use windows::{
     Win32::System::Memory::{PAGE_EXECUTE_READWRITE, PAGE_PROTECTION_FLAGS, VirtualProtect},
     core::PCWSTR,
 };
 
 use crate::{
-    config::CrosshairKind,
+    config::{CrosshairBlend, CrosshairKind, FovCorrection, HitMarkerStyle},
     hooks::install::hook,
     program::Program,
     rva::{
         ADD_PIXEL_SHADER_RVA, CB_FISHEYE_HOOK_RVA, GX_FFX_DRAW_CONTEXT_RVA, GX_FFX_DRAW_PASS_RVA,
         USES_DITHERING_RVA,
     },
+    shaders::{
+        crosshair::CrosshairShape,
+        overrides::{ShaderOverride, ShaderRegistry},
+        params::{ShaderFlag, ShaderParams, ShaderParamsLock},
+    },
 };
 
+pub mod crosshair;
+pub mod mapping;
+pub mod overrides;
+pub mod params;
 pub mod screen;
 
-static TONE_MAP_HOOK: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/ToneMap_PostHook.ppo"));
+/// Game pixel shaders replaced when the game registers them.
+///
+/// Run with `RUST_LOG=debug` to log the names of all registered pixel shaders.
+static SHADER_OVERRIDES: ShaderRegistry<1> = ShaderRegistry::new([ShaderOverride::new(
+    "ToneMap_PostOETFPS",
+    "ToneMap_PostHook.ppo",
+    include_bytes!(concat!(env!("OUT_DIR"), "/ToneMap_PostHook.ppo")),
+)]);
+
+type AddPixelShader = unsafe extern "C" fn(*mut c_void, PCWSTR, *const u8, usize) -> *mut c_void;
 
 pub fn hook_shaders(program: Program) -> eyre::Result<()> {
     unsafe {
-        let add_pixel_shader = program.derva_ptr::<unsafe extern "C" fn(
-            *mut c_void,
-            PCWSTR,
-            *const u8,
-            usize,
-        ) -> *mut c_void>(ADD_PIXEL_SHADER_RVA);
+        let add_pixel_shader = program.derva_ptr::<AddPixelShader>(ADD_PIXEL_SHADER_RVA);
 
         hook(add_pixel_shader, |original| {
             move |repository, name, mut blob, mut len| {
-                if name
-                    .to_string()
-                    .is_ok_and(|name| name == "ToneMap_PostOETFPS")
-                {
-                    blob = TONE_MAP_HOOK.as_ptr();
-                    len = TONE_MAP_HOOK.len();
+                let shader = name.to_string().ok().and_then(|name| {
+                    log::debug!("adding pixel shader {name}");
+                    SHADER_OVERRIDES.find(&name)
+                });
+
+                if let Some(shader) = shader {
+                    let replacement = shader.blob();
+                    blob = replacement.as_ptr();
+                    len = replacement.len();
                 }
 
                 original(repository, name, blob, len)
@@ -65,56 +85,162 @@ pub fn hook_shaders(program: Program) -> eyre::Result<()> {
     }
 }
 
-static SHADER_FLAGS: AtomicU32 = AtomicU32::new(0);
-static SHADER_PARAMS: AtomicU64 = AtomicU64::new(0);
-static SHADER_PARAMS2: AtomicU64 = AtomicU64::new(0);
+static SHADER_PARAMS: ShaderParamsLock = ShaderParamsLock::new(ShaderParams::DEFAULT);
+static STAGED_SHADER_PARAMS: Mutex<ShaderParams> = Mutex::new(ShaderParams::DEFAULT);
+static ASPECT_RATIO: AtomicU32 = AtomicU32::new((16.0f32 / 9.0).to_bits());
 
+/// `panini` holds the Panini projection distance and vertical compensation.
+///
+/// `strength` and `cylindricity` only apply to fisheye and barrel correction.
 pub fn enable_fov_correction(
     state: bool,
+    mode: FovCorrection,
     strength: f32,
     cylindricity: f32,
-    use_barrel: bool,
+    panini: (f32, f32),
     horizontal_fov: f32,
 ) {
-    let state = state && strength > 0.05;
+    let projection = match mode {
+        FovCorrection::Panini => 1,
+        FovCorrection::Stereographic => 2,
+        _ => 0,
+    };
 
-    set_shader_flag(state, 0);
-    set_shader_flag(use_barrel, 1);
+    let state = state && mode != FovCorrection::None && (strength > 0.05 || projection != 0);
 
-    if state {
-        let cylindricity = cylindricity.to_bits() as u64;
+    update_shader_params(|params| {
+        params.set_flag(ShaderFlag::FOV_CORRECTION, state);
+        params.set_flag(ShaderFlag::BARREL, mode == FovCorrection::Barrel);
+        params.set_bits(ShaderFlag::PROJECTION, projection);
 
-        let strength_width_ratio = strength * f32::tan(horizontal_fov * 0.5);
-        let strength_width_ratio = strength_width_ratio.to_bits() as u64;
+        if state {
+            let tangent = f32::tan(horizontal_fov * 0.5);
 
-        SHADER_PARAMS.store(
-            cylindricity | (strength_width_ratio << 32),
-            Ordering::Relaxed,
-        );
+            params.correction = [cylindricity, strength * tangent];
+            params.fov_tangent = tangent;
+            params.panini = [panini.0, panini.1];
+        }
+    });
+}
+
+/// Sets the render aspect ratio for CPU-side screen coordinate corrections.
+pub fn set_aspect_ratio(aspect_ratio: f32) {
+    if aspect_ratio.is_finite() && aspect_ratio > 0.0 {
+        ASPECT_RATIO.store(aspect_ratio.to_bits(), Ordering::Relaxed);
     }
 }
 
 pub fn set_crosshair(crosshair: CrosshairKind, scale: (f32, f32)) {
-    let _ = SHADER_FLAGS.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |value| {
-        Some(value & !0b11100 | (crosshair as u32 & 0b111) << 2)
+    update_shader_params(|params| {
+        params.set_bits(ShaderFlag::CROSSHAIR_KIND, crosshair as u32);
+        params.crosshair_scale_reciprocal = [scale.0.recip(), scale.1.recip()];
     });
+}
+
+/// Sharpens the image where FOV correction magnifies it, scaled by the local magnification.
+pub fn set_sharpening(strength: f32) {
+    let state = strength > 0.001;
 
-    let rscale_x = scale.0.recip().to_bits() as u64;
-    let rscale_y = scale.1.recip().to_bits() as u64;
+    update_shader_params(|params| {
+        params.set_flag(ShaderFlag::SHARPENING, state);
 
-    SHADER_PARAMS2.store(rscale_x | (rscale_y << 32), Ordering::Relaxed);
+        if state {
+            params.sharpening = strength;
+        }
+    });
 }
 
-fn get_fov_correction() -> (f32, f32) {
-    let params = SHADER_PARAMS.load(Ordering::Relaxed);
+/// Scales the game's chromatic aberration, 1.0 leaves it unchanged.
+pub fn set_chromatic_aberration(scale: f32) {
+    update_shader_params(|params| {
+        params.set_flag(ShaderFlag::CHROMATIC_ABERRATION_SCALE, scale != 1.0);
+        params.chromatic_aberration_scale = scale;
+    });
+}
+
+pub fn set_vignette(strength: f32) {
+    let state = strength > 0.001;
+
+    update_shader_params(|params| {
+        params.set_flag(ShaderFlag::VIGNETTE, state);
+
+        if state {
+            params.vignette_strength = strength;
+        }
+    });
+}
+
+/// Moves the crosshair away from the screen center by `offset` in normalized screen coordinates.
+pub fn set_crosshair_offset(offset: Vec2) {
+    update_shader_params(|params| params.crosshair_offset = offset.to_array());
+}
 
-    let cylindricity = f32::from_bits(params as u32);
-    let strength_width_ratio = f32::from_bits((params >> 32) as u32);
+/// Sets the blend mode and colors of the crosshair.
+///
+/// Colors are packed as 8-bit RGBA with red in the lowest byte.
+/// The outline width is in fractions of the screen height.
+pub fn set_crosshair_style(
+    blend: CrosshairBlend,
+    color: u32,
+    outline_color: u32,
+    outline_width: f32,
+) {
+    update_shader_params(|params| {
+        params.set_bits(ShaderFlag::CROSSHAIR_BLEND, blend as u32);
+        params.crosshair_color = color;
+        params.crosshair_outline_color = outline_color;
+        params.crosshair_outline_width = outline_width;
+    });
+}
 
-    (cylindricity, strength_width_ratio)
+pub fn set_crosshair_shape(shape: &CrosshairShape) {
+    update_shader_params(|params| params.crosshair_shape = shape.0);
+}
+
+/// Shows a hit marker with `style`, animated by `progress` from 0.0 to 1.0.
+pub fn set_hit_marker(style: Option<HitMarkerStyle>, progress: f32, color: u32) {
+    let style = style.map_or(0, |style| style as u32 + 1);
+
+    update_shader_params(|params| {
+        params.set_bits(ShaderFlag::HIT_MARKER_STYLE, style);
+        params.hit_marker_progress = progress;
+        params.hit_marker_color = color;
+    });
+}
+
+pub fn set_crosshair_opacity(opacity: f32) {
+    update_shader_params(|params| params.crosshair_opacity = opacity.clamp(0.0, 1.0));
+}
+
+/// Returns the shader parameters last published to the render thread.
+pub fn shader_params() -> ShaderParams {
+    SHADER_PARAMS.read()
+}
+
+fn update_shader_params(f: impl FnOnce(&mut ShaderParams)) {
+    let mut params = STAGED_SHADER_PARAMS.lock().unwrap();
+
+    f(&mut params);
+
+    SHADER_PARAMS.write(&params);
+}
+
+fn get_aspect_ratio() -> f32 {
+    f32::from_bits(ASPECT_RATIO.load(Ordering::Relaxed))
 }
 
 unsafe fn hook_shader_cb(program: Program) -> eyre::Result<()> {
+    /// Copies the parameter block to the constant buffer and returns the shader flags.
+    extern "C" fn copy_shader_params(cb: *mut ShaderParams) -> u32 {
+        let params = SHADER_PARAMS.read();
+
+        unsafe {
+            cb.write_unaligned(params);
+        }
+
+        params.flags
+    }
+
     #[unsafe(naked)]
     extern "C" fn fisheye_distortion_cb_hook() {
         naked_asm! {
@@ -123,23 +249,43 @@ unsafe fn hook_shader_cb(program: Program) -> eyre::Result<()> {
             "lea rdx,[rbp-0x80]",
             "mov rcx,[r14+0x08]",
             // ...original code end.
-            // Forward the flags to the constant buffer (see "shaders/ToneMap_PostHook.hlsl").
-            "mov eax,[rip+{}]",
-            "mov [rbp-0x44],eax",
+            // Preserve the volatile registers, keeping the stack 16 byte aligned.
+            "push rcx",
+            "push rdx",
+            "push r8",
+            "push r9",
+            "push r10",
+            "push r11",
+            "sub rsp,0x88",
+            "movdqu [rsp+0x20],xmm0",
+            "movdqu [rsp+0x30],xmm1",
+            "movdqu [rsp+0x40],xmm2",
+            "movdqu [rsp+0x50],xmm3",
+            "movdqu [rsp+0x60],xmm4",
+            "movdqu [rsp+0x70],xmm5",
+            // Copy the parameter block over the constant buffer fields from offset 0x10
+            // to 0xe0 (see `ShaderParams::REPLACED_FIELDS`).
+            "lea rcx,[rbp-0x40]",
+            "call {copy}",
+            "movdqu xmm0,[rsp+0x20]",
+            "movdqu xmm1,[rsp+0x30]",
+            "movdqu xmm2,[rsp+0x40]",
+            "movdqu xmm3,[rsp+0x50]",
+            "movdqu xmm4,[rsp+0x60]",
+            "movdqu xmm5,[rsp+0x70]",
+            "add rsp,0x88",
+            "pop r11",
+            "pop r10",
+            "pop r9",
+            "pop r8",
+            "pop rdx",
+            "pop rcx",
             // Force the shader on.
             "test eax,eax",
             "setne al",
             "mov [r15+0xcb0],al",
-            // Forward the screen width ratio to the shader (see above).
-            "mov rax,[rip+{}]",
-            "mov [rbp+0xa8],rax",
-            // Forward the crosshair size.
-            "mov rax,[rip+{}]",
-            "mov [rbp+0x148],rax",
             "ret",
-            sym SHADER_FLAGS,
-            sym SHADER_PARAMS,
-            sym SHADER_PARAMS2,
+            copy = sym copy_shader_params,
         }
     }
 
@@ -221,15 +367,3 @@ static ENABLE_DITHERING: AtomicBool = AtomicBool::new(true);
 pub fn enable_dithering(state: bool) {
     ENABLE_DITHERING.store(state, Ordering::Relaxed);
 }
-
-fn set_shader_flag(state: bool, pos: u32) -> u32 {
-    let flag = 1 << pos;
-    match state {
-        true => SHADER_FLAGS.fetch_or(flag, Ordering::Relaxed),
-        false => SHADER_FLAGS.fetch_and(!flag, Ordering::Relaxed),
-    }
-}
-
-fn get_shader_flag(pos: u32) -> bool {
-    (SHADER_FLAGS.load(Ordering::Relaxed) >> pos) & 1 != 0
-}
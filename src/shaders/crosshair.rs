@@ -0,0 +1,254 @@
+use std::f32::consts::FRAC_1_SQRT_2;
+
+use glam::Vec2;
+
+use crate::config::CrosshairPrimitive;
+
+/// A custom crosshair shape, encoded for `g_Erfps.crosshairShape` in "shaders/ToneMap_PostHook.hlsl".
+///
+/// Every primitive takes two words:
+/// - bits 0-3 of the first word hold the kind, bits 16-31 the thickness,
+/// - bits 0-15 of the second word hold the gap or radius, bits 16-31 the length.
+///
+/// Sizes are fixed point, in [`CrosshairShape::UNITS_PER_PIXEL`] of a pixel at 1080p.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CrosshairShape(pub [u32; Self::MAX_PRIMITIVES * 2]);
+
+impl CrosshairShape {
+    pub const MAX_PRIMITIVES: usize = 8;
+
+    pub const UNITS_PER_PIXEL: f32 = 64.0;
+
+    const KIND_LINES: u32 = 1;
+    const KIND_RING: u32 = 2;
+    const KIND_DOT: u32 = 3;
+    const KIND_CHEVRON: u32 = 4;
+    const KIND_T: u32 = 5;
+
+    /// Sizes in fractions of the screen height.
+    const UNIT: f32 = 1.0 / (Self::UNITS_PER_PIXEL * 1080.0);
+
+    /// Encodes up to [`CrosshairShape::MAX_PRIMITIVES`] primitives, the rest are dropped.
+    pub fn encode(primitives: &[CrosshairPrimitive]) -> Self {
+        let mut words = [0; Self::MAX_PRIMITIVES * 2];
+
+        for (primitive, words) in primitives.iter().zip(words.chunks_exact_mut(2)) {
+            let (kind, thickness, gap, length) = match *primitive {
+                CrosshairPrimitive::Lines {
+                    gap,
+                    length,
+                    thickness,
+                } => (Self::KIND_LINES, thickness, gap, length),
+                CrosshairPrimitive::Ring { radius, thickness } => {
+                    (Self::KIND_RING, thickness, radius, 0.0)
+                }
+                CrosshairPrimitive::Dot { radius } => (Self::KIND_DOT, 0.0, radius, 0.0),
+                CrosshairPrimitive::Chevron {
+                    gap,
+                    length,
+                    thickness,
+                } => (Self::KIND_CHEVRON, thickness, gap, length),
+                CrosshairPrimitive::T {
+                    gap,
+                    length,
+                    thickness,
+                } => (Self::KIND_T, thickness, gap, length),
+            };
+
+            words[0] = kind | encode_size(thickness) << 16;
+            words[1] = encode_size(gap) | encode_size(length) << 16;
+        }
+
+        Self(words)
+    }
+
+    /// Computes the coverage of the shape at `p`, relative to the crosshair center in fractions
+    /// of the screen height, with anti-aliasing over `pixel_size`. `grow` expands the shape.
+    ///
+    /// Mirrors `CustomCrosshairAlpha` in "shaders/ToneMap_PostHook.hlsl".
+    pub fn alpha(&self, p: Vec2, pixel_size: f32, grow: f32) -> f32 {
+        let mut alpha = 0.0f32;
+
+        for words in self.0.chunks_exact(2) {
+            let kind = words[0] & 0xf;
+            if kind == 0 {
+                continue;
+            }
+
+            let thickness = (words[0] >> 16) as f32 * Self::UNIT;
+            let gap = (words[1] & 0xffff) as f32 * Self::UNIT;
+            let length = (words[1] >> 16) as f32 * Self::UNIT;
+
+            let (distance, half_width) = match kind {
+                Self::KIND_LINES => {
+                    let q = p.abs();
+                    let d = f32::min(
+                        sd_segment(q, Vec2::new(gap, 0.0), Vec2::new(gap + length, 0.0)),
+                        sd_segment(q, Vec2::new(0.0, gap), Vec2::new(0.0, gap + length)),
+                    );
+                    (d, thickness * 0.5)
+                }
+                Self::KIND_RING => ((p.length() - gap).abs(), thickness * 0.5),
+                Self::KIND_DOT => (p.length(), gap),
+                Self::KIND_CHEVRON => {
+                    let q = Vec2::new(p.x.abs(), p.y);
+                    let arm = length * FRAC_1_SQRT_2;
+                    let d = sd_segment(q, Vec2::new(0.0, gap), Vec2::new(arm, gap + arm));
+                    (d, thickness * 0.5)
+                }
+                Self::KIND_T => {
+                    let q = Vec2::new(p.x.abs(), p.y);
+                    let d = f32::min(
+                        sd_segment(q, Vec2::new(gap, 0.0), Vec2::new(gap + length, 0.0)),
+                        sd_segment(p, Vec2::new(0.0, gap), Vec2::new(0.0, gap + length)),
+                    );
+                    (d, thickness * 0.5)
+                }
+                _ => continue,
+            };
+
+            let d = distance - half_width - grow;
+            alpha = alpha.max((0.5 - d / pixel_size).clamp(0.0, 1.0));
+        }
+
+        alpha
+    }
+
+    /// Rasterizes the shape into a `size` by `size` coverage image centered on the crosshair,
+    /// as it would appear on a screen `screen_height` pixels tall.
+    pub fn rasterize(&self, size: usize, screen_height: f32) -> Vec<f32> {
+        let pixel_size = screen_height.recip();
+        let center = size as f32 * 0.5;
+
+        (0..size * size)
+            .map(|i| {
+                let pixel = Vec2::new((i % size) as f32, (i / size) as f32) + 0.5;
+                self.alpha((pixel - center) * pixel_size, pixel_size, 0.0)
+            })
+            .collect()
+    }
+}
+
+fn encode_size(pixels: f32) -> u32 {
+    (pixels * CrosshairShape::UNITS_PER_PIXEL)
+        .round()
+        .clamp(0.0, u16::MAX as f32) as u32
+}
+
+fn sd_segment(p: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let pa = p - a;
+    let ba = b - a;
+    let h = (pa.dot(ba) / ba.dot(ba)).clamp(0.0, 1.0);
+    (pa - ba * h).length()
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+
+    use super::CrosshairShape;
+    use crate::config::CrosshairPrimitive;
+
+    const PIXEL: f32 = 1.0 / 1080.0;
+
+    fn alpha_at(shape: &CrosshairShape, x: f32, y: f32) -> f32 {
+        shape.alpha(Vec2::new(x, y) * PIXEL, PIXEL, 0.0)
+    }
+
+    #[test]
+    fn dot() {
+        let shape = CrosshairShape::encode(&[CrosshairPrimitive::Dot { radius: 2.0 }]);
+
+        assert_eq!(alpha_at(&shape, 0.0, 0.0), 1.0);
+        assert!((alpha_at(&shape, 2.0, 0.0) - 0.5).abs() < 1e-3);
+        assert_eq!(alpha_at(&shape, 4.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn lines_leave_a_gap() {
+        let shape = CrosshairShape::encode(&[CrosshairPrimitive::Lines {
+            gap: 4.0,
+            length: 6.0,
+            thickness: 2.0,
+        }]);
+
+        assert_eq!(alpha_at(&shape, 0.0, 0.0), 0.0);
+
+        for (x, y) in [(7.0, 0.0), (-7.0, 0.0), (0.0, 7.0), (0.0, -7.0)] {
+            assert_eq!(alpha_at(&shape, x, y), 1.0);
+        }
+
+        assert_eq!(alpha_at(&shape, 12.0, 0.0), 0.0);
+        assert_eq!(alpha_at(&shape, 7.0, 7.0), 0.0);
+    }
+
+    #[test]
+    fn ring() {
+        let shape = CrosshairShape::encode(&[CrosshairPrimitive::Ring {
+            radius: 8.0,
+            thickness: 2.0,
+        }]);
+
+        assert_eq!(alpha_at(&shape, 0.0, 0.0), 0.0);
+        assert_eq!(alpha_at(&shape, 0.0, 8.0), 1.0);
+        assert_eq!(alpha_at(&shape, -8.0, 0.0), 1.0);
+        assert_eq!(alpha_at(&shape, 11.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn t_has_no_top_arm() {
+        let shape = CrosshairShape::encode(&[CrosshairPrimitive::T {
+            gap: 3.0,
+            length: 6.0,
+            thickness: 2.0,
+        }]);
+
+        assert_eq!(alpha_at(&shape, 6.0, 0.0), 1.0);
+        assert_eq!(alpha_at(&shape, -6.0, 0.0), 1.0);
+        // Screen space y points down.
+        assert_eq!(alpha_at(&shape, 0.0, 6.0), 1.0);
+        assert_eq!(alpha_at(&shape, 0.0, -6.0), 0.0);
+    }
+
+    #[test]
+    fn chevron_is_below_center() {
+        let shape = CrosshairShape::encode(&[CrosshairPrimitive::Chevron {
+            gap: 4.0,
+            length: 8.0,
+            thickness: 2.0,
+        }]);
+
+        assert_eq!(alpha_at(&shape, 0.0, 4.0), 1.0);
+        assert_eq!(alpha_at(&shape, 4.0, 8.0), 1.0);
+        assert_eq!(alpha_at(&shape, -4.0, 8.0), 1.0);
+        assert_eq!(alpha_at(&shape, 0.0, -4.0), 0.0);
+    }
+
+    #[test]
+    fn grow_expands_the_shape() {
+        let shape = CrosshairShape::encode(&[CrosshairPrimitive::Dot { radius: 2.0 }]);
+        let p = Vec2::new(3.5, 0.0) * PIXEL;
+
+        assert_eq!(shape.alpha(p, PIXEL, 0.0), 0.0);
+        assert_eq!(shape.alpha(p, PIXEL, 2.0 * PIXEL), 1.0);
+    }
+
+    #[test]
+    fn extra_primitives_are_dropped() {
+        let primitives = [CrosshairPrimitive::Dot { radius: 1.0 }; 10];
+        let shape = CrosshairShape::encode(&primitives);
+
+        assert!(shape.0.chunks_exact(2).all(|words| words[0] & 0xf == 3));
+    }
+
+    #[test]
+    fn rasterize_is_centered() {
+        let shape = CrosshairShape::encode(&[CrosshairPrimitive::Dot { radius: 1.0 }]);
+        let image = shape.rasterize(8, 1080.0);
+
+        assert_eq!(image.len(), 64);
+        assert!(image[3 * 8 + 3] > 0.0 && image[4 * 8 + 4] > 0.0);
+        assert_eq!(image[0], 0.0);
+        assert_eq!(image[63], 0.0);
+    }
+}
@@ -0,0 +1,496 @@
+//! Rust ports of the `MapUv*` functions in "shaders/ToneMap_PostHook.hlsl" and their inverses.
+//!
+//! The shader maps screen coordinates of the corrected image to the rendered image,
+//! the inverses map rendered coordinates to where they appear on screen.
+
+use glam::{Mat2, Vec2};
+
+use crate::{config::FovCorrection, shaders::screen::ScreenCorrection};
+
+impl ScreenCorrection {
+    /// Inverses iterate until the mapped point is within this distance of the target.
+    pub const TOLERANCE: f32 = 1e-6;
+
+    const MAX_ITERATIONS: usize = 16;
+
+    /// Maps normalized screen coordinates of the corrected image to the rendered image,
+    /// like the shader does.
+    pub fn map_uv(&self, uv: Vec2) -> Vec2 {
+        match self.mode {
+            FovCorrection::None => uv,
+            FovCorrection::Fisheye => self.map_uv_fisheye(uv),
+            FovCorrection::Barrel => self.map_uv_barrel(uv),
+            FovCorrection::Panini => self.map_uv_panini(uv),
+            FovCorrection::Stereographic => self.map_uv_stereographic(uv),
+        }
+    }
+
+    /// Maps normalized screen coordinates of the rendered image to the corrected image.
+    pub fn unmap_uv(&self, xy: Vec2) -> Vec2 {
+        match self.mode {
+            FovCorrection::None => xy,
+            FovCorrection::Fisheye => self.unmap_uv_fisheye(xy),
+            FovCorrection::Barrel => self.unmap_uv_barrel(xy),
+            FovCorrection::Panini => self.unmap_uv_panini(xy),
+            FovCorrection::Stereographic => self.unmap_uv_stereographic(xy),
+        }
+    }
+
+    /// Mirrors `MapUvFisheye`.
+    fn map_uv_fisheye(&self, uv: Vec2) -> Vec2 {
+        let c = uv - 0.5;
+
+        let r2 = c.x * c.x;
+        let strength = self.strength_width_ratio;
+
+        let f = 1.0 + strength * r2.sqrt() * r2;
+        let f_max = 1.0 + strength * 0.125;
+
+        c * f / f_max + 0.5
+    }
+
+    /// Solves `x = y + strength * y^4` for the horizontal coordinate with Newton's method,
+    /// the vertical coordinate is scaled by the same factor.
+    fn unmap_uv_fisheye(&self, xy: Vec2) -> Vec2 {
+        let strength = self.strength_width_ratio;
+        let k_max = 1.0 + strength * 0.125;
+
+        let uvk = (xy - 0.5) * k_max;
+
+        if uvk.x == 0.0 {
+            return uvk + 0.5;
+        }
+
+        let x = uvk.x.abs();
+        let mut y = x + 0.07 * (strength * x * x) - 0.5 * (strength * x * x) * x;
+
+        let f = |y: f32| (strength * (y * y)) * (y * y) + y - x;
+        let f_prime = |y: f32| 4.0 * (strength * (y * y)) * y + 1.0;
+
+        for _ in 0..Self::MAX_ITERATIONS {
+            let error = f(y);
+            if error.abs() < Self::TOLERANCE {
+                break;
+            }
+
+            y -= error / f_prime(y);
+        }
+
+        Vec2::new(y.copysign(uvk.x), uvk.y * y / x) + 0.5
+    }
+
+    // Source: https://www.decarpentier.nl/lens-distortion
+    //
+    // Copyright (c) 2015, Giliam de Carpentier
+    // All rights reserved.
+    //
+    // Redistribution and use in source and binary forms, with or without modification, are permitted provided that the following conditions are met:
+    //
+    // 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following disclaimer.
+    //
+    // 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the following disclaimer in the
+    // documentation and/or other materials provided with the distribution.
+    //
+    // THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED
+    // TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR
+    // CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+    // PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
+    // LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+    // SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+    //
+    // Mirrors `MapUvBarrel`.
+    fn map_uv_barrel(&self, uv: Vec2) -> Vec2 {
+        let aspect_ratio = self.aspect_ratio;
+        let cylindrical_ratio = self.cylindricity;
+
+        let scaled_height = self.strength_width_ratio / aspect_ratio;
+        let cyl_aspect_ratio = aspect_ratio * cylindrical_ratio;
+        let aspect_diag_sq = aspect_ratio * aspect_ratio + 1.0;
+        let diag_sq = scaled_height * scaled_height * aspect_diag_sq;
+        let signed_uv = 2.0 * uv - 1.0;
+
+        let z = 0.5 * (diag_sq + 1.0).sqrt() + 0.5;
+        let ny = (z - 1.0) / (cyl_aspect_ratio * cyl_aspect_ratio + 1.0);
+
+        let uv_dot = ny.sqrt() * Vec2::new(cyl_aspect_ratio, 1.0) * signed_uv;
+        let uv_dot_sq = uv_dot.dot(uv_dot);
+
+        let uvp = uv + 0.5 * z - 0.5 - uv_dot_sq * 0.5;
+        uvp / (z - uv_dot_sq)
+    }
+
+    /// Starts from the closed form inverse and refines it against [`Self::map_uv_barrel`].
+    fn unmap_uv_barrel(&self, xy: Vec2) -> Vec2 {
+        let aspect_ratio = self.aspect_ratio;
+        let scaled_height = self.strength_width_ratio / aspect_ratio;
+        let cyl_aspect_ratio = aspect_ratio * self.cylindricity;
+        let cyl_aspect_ratio_sq = cyl_aspect_ratio * cyl_aspect_ratio;
+        let aspect_diag_sq = aspect_ratio * aspect_ratio + 1.0;
+        let diag_sq = scaled_height * scaled_height * aspect_diag_sq;
+        let signed_uv = 2.0 * xy - 1.0;
+
+        let z = 0.5 * (diag_sq + 1.0).sqrt() + 0.5;
+        let ny = (z - 1.0) / (cyl_aspect_ratio_sq + 1.0);
+        let nx = cyl_aspect_ratio_sq * ny;
+        let p_sq = signed_uv * signed_uv;
+        let ivp = (0.25 + z * (nx * p_sq.x + ny * p_sq.y)).sqrt();
+
+        let guess = z * signed_uv / (0.5 + ivp) * 0.5 + 0.5;
+
+        self.refine(xy, guess, Self::map_uv_barrel)
+    }
+
+    /// Mirrors `MapUvPanini`.
+    fn map_uv_panini(&self, uv: Vec2) -> Vec2 {
+        let aspect = Vec2::new(1.0, self.aspect_ratio.recip());
+        let d = self.panini_distance;
+
+        let p = (2.0 * uv - 1.0) * self.panini_extent() * aspect;
+
+        // Solve x = S * sin(lon), S = (d + 1) / (d + cos(lon)) for cos(lon).
+        let k = p.x * p.x / ((d + 1.0) * (d + 1.0));
+        let disc = k * k * d * d - (k + 1.0) * (k * d * d - 1.0);
+        let cos_lon = (-k * d + disc.max(0.0).sqrt()) / (k + 1.0);
+        let scale_cos_lon = (d + 1.0) / (d + cos_lon) * cos_lon;
+
+        let xy = Vec2::new(
+            p.x / scale_cos_lon,
+            p.y * self.panini_vertical_scale(scale_cos_lon),
+        );
+
+        xy / (self.tangent * aspect) * 0.5 + 0.5
+    }
+
+    fn unmap_uv_panini(&self, xy: Vec2) -> Vec2 {
+        let aspect_ratio = self.aspect_ratio;
+        let signed_uv = 2.0 * xy - 1.0;
+
+        let x = signed_uv.x * self.tangent;
+        let y = signed_uv.y * self.tangent / aspect_ratio;
+
+        let cos_lon = (1.0 + x * x).sqrt().recip();
+        let scale = (self.panini_distance + 1.0) / (self.panini_distance + cos_lon);
+
+        let px = scale * x * cos_lon;
+        let py = y / self.panini_vertical_scale(scale * cos_lon);
+
+        let extent = self.panini_extent();
+
+        Vec2::new(px, py * aspect_ratio) / extent * 0.5 + 0.5
+    }
+
+    /// The Panini coordinate at the right edge of the screen,
+    /// zoomed in for the rendered image to cover the screen corners.
+    fn panini_extent(&self) -> f32 {
+        let tangent = self.tangent;
+
+        let cos_lon = (1.0 + tangent * tangent).sqrt().recip();
+        let scale = (self.panini_distance + 1.0) / (self.panini_distance + cos_lon);
+
+        let extent = scale * tangent * cos_lon;
+        let vertical_scale = self.panini_vertical_scale(scale * cos_lon);
+
+        extent * f32::min(1.0, tangent / (extent * vertical_scale))
+    }
+
+    /// The ratio of rectilinear to Panini vertical coordinates,
+    /// blended towards 1.0 (straight horizontal lines) by the vertical compensation.
+    fn panini_vertical_scale(&self, scale_cos_lon: f32) -> f32 {
+        1.0 + (scale_cos_lon.recip() - 1.0) * (1.0 - self.panini_compensation)
+    }
+
+    /// Mirrors `MapUvStereographic`.
+    fn map_uv_stereographic(&self, uv: Vec2) -> Vec2 {
+        let aspect = Vec2::new(1.0, self.aspect_ratio.recip());
+
+        let p = (2.0 * uv - 1.0) * self.stereographic_extent() * aspect;
+        let xy = p / (1.0 - p.length_squared() * 0.25);
+
+        xy / (self.tangent * aspect) * 0.5 + 0.5
+    }
+
+    fn unmap_uv_stereographic(&self, xy: Vec2) -> Vec2 {
+        let aspect_ratio = self.aspect_ratio;
+        let signed_uv = 2.0 * xy - 1.0;
+
+        let p = signed_uv * Vec2::new(self.tangent, self.tangent / aspect_ratio);
+        let r = p.length();
+
+        if r == 0.0 {
+            return xy;
+        }
+
+        let p = p * (stereographic_radius(r) / r);
+        let extent = self.stereographic_extent();
+
+        Vec2::new(p.x, p.y * aspect_ratio) / extent * 0.5 + 0.5
+    }
+
+    /// The stereographic coordinate at the right edge of the screen,
+    /// zoomed in for the rendered image to cover the screen corners.
+    fn stereographic_extent(&self) -> f32 {
+        let diagonal = (1.0 + self.aspect_ratio.powi(-2)).sqrt();
+
+        let extent = stereographic_radius(self.tangent);
+        let corner = stereographic_radius(self.tangent * diagonal);
+
+        f32::min(extent, corner / diagonal)
+    }
+
+    /// Refines `guess` with Newton's method until `map(guess)` is close to `target`.
+    fn refine(&self, target: Vec2, mut guess: Vec2, map: fn(&Self, Vec2) -> Vec2) -> Vec2 {
+        const H: f32 = 1e-3;
+
+        for _ in 0..Self::MAX_ITERATIONS {
+            let error = map(self, guess) - target;
+            if error.abs().max_element() < Self::TOLERANCE {
+                break;
+            }
+
+            let dx = (map(self, guess + Vec2::new(H, 0.0)) - map(self, guess - Vec2::new(H, 0.0)))
+                / (2.0 * H);
+            let dy = (map(self, guess + Vec2::new(0.0, H)) - map(self, guess - Vec2::new(0.0, H)))
+                / (2.0 * H);
+
+            let jacobian = Mat2::from_cols(dx, dy);
+            if jacobian.determinant().abs() < f32::EPSILON {
+                break;
+            }
+
+            guess -= jacobian.inverse() * error;
+        }
+
+        guess
+    }
+}
+
+/// `2 tan(θ / 2)` for a rectilinear radius of `tan(θ)`.
+fn stereographic_radius(r: f32) -> f32 {
+    2.0 * r / (1.0 + (1.0 + r * r).sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+
+    use crate::{config::FovCorrection, shaders::screen::ScreenCorrection};
+
+    /// One pixel at 4K.
+    const PIXEL: f32 = 1.0 / 3840.0;
+
+    const ASPECT_RATIOS: [f32; 4] = [16.0 / 10.0, 16.0 / 9.0, 21.0 / 9.0, 32.0 / 9.0];
+
+    const FOVS: [f32; 5] = [45.0, 70.0, 90.0, 110.0, 130.0];
+
+    fn grid() -> impl Iterator<Item = Vec2> {
+        const STEPS: usize = 12;
+
+        (0..=STEPS * STEPS)
+            .map(|i| Vec2::new((i % (STEPS + 1)) as f32, (i / (STEPS + 1)) as f32) / STEPS as f32)
+    }
+
+    fn assert_round_trip(correction: ScreenCorrection) {
+        for xy in grid() {
+            let corrected = correction.unmap_uv(xy);
+            let error = correction.map_uv(corrected) - xy;
+
+            assert!(
+                error.abs().max_element() < PIXEL,
+                "{correction:?}: {xy} -> {corrected}, error {error}"
+            );
+        }
+    }
+
+    fn corrections(mode: FovCorrection) -> impl Iterator<Item = ScreenCorrection> {
+        ASPECT_RATIOS.into_iter().flat_map(move |aspect_ratio| {
+            FOVS.into_iter().flat_map(move |fov| {
+                let tangent = f32::tan(fov.to_radians() * 0.5);
+
+                (0..=10).flat_map(move |i| {
+                    let t = i as f32 / 10.0;
+
+                    (0..=4).map(move |j| ScreenCorrection {
+                        mode,
+                        aspect_ratio,
+                        // See `From<toml::Config>` for `Config`.
+                        cylindricity: j as f32 / 4.0 * 1.5 + 0.5,
+                        strength_width_ratio: t * tangent,
+                        tangent,
+                        panini_distance: t * 2.0,
+                        panini_compensation: j as f32 / 4.0,
+                    })
+                })
+            })
+        })
+    }
+
+    /// FNV-1a of the `MapUv*` functions in "shaders/ToneMap_PostHook.hlsl", which
+    /// `GOLDEN_VECTORS` were evaluated from.
+    const SHADER_FINGERPRINT: u64 = 0xab80_7363_a0c0_6fb3;
+
+    /// `MapUv*` outputs for fixed inputs, evaluated from the shader expressions in double
+    /// precision, with `g_vCameraParam` holding the aspect ratio and its reciprocal.
+    const GOLDEN_VECTORS: [(FovCorrection, [(Vec2, Vec2); 4]); 4] = [
+        (
+            FovCorrection::Fisheye,
+            [
+                (Vec2::new(0.1, 0.2), Vec2::new(0.1136186, 0.210214)),
+                (Vec2::new(0.9, 0.75), Vec2::new(0.8863814, 0.7414884)),
+                (Vec2::new(0.65, 0.4), Vec2::new(0.6398174, 0.4067884)),
+                (Vec2::new(0.0, 1.0), Vec2::new(0.0, 1.0)),
+            ],
+        ),
+        (
+            FovCorrection::Barrel,
+            [
+                (Vec2::new(0.1, 0.2), Vec2::new(0.1217597, 0.2163197)),
+                (Vec2::new(0.9, 0.75), Vec2::new(0.8773065, 0.7358166)),
+                (Vec2::new(0.65, 0.4), Vec2::new(0.6327706, 0.4114863)),
+                (Vec2::new(0.0, 1.0), Vec2::new(0.0, 1.0)),
+            ],
+        ),
+        (
+            FovCorrection::Panini,
+            [
+                (Vec2::new(0.1, 0.2), Vec2::new(0.1472104, 0.2583524)),
+                (Vec2::new(0.9, 0.75), Vec2::new(0.8527896, 0.701373)),
+                (Vec2::new(0.65, 0.4), Vec2::new(0.6120851, 0.4261878)),
+                (Vec2::new(0.0, 1.0), Vec2::new(0.0, 0.9322525)),
+            ],
+        ),
+        (
+            FovCorrection::Stereographic,
+            [
+                (Vec2::new(0.1, 0.2), Vec2::new(0.1829558, 0.2622168)),
+                (Vec2::new(0.9, 0.75), Vec2::new(0.8130274, 0.6956421)),
+                (Vec2::new(0.65, 0.4), Vec2::new(0.5958717, 0.4360855)),
+                (Vec2::new(0.0, 1.0), Vec2::new(0.0, 1.0)),
+            ],
+        ),
+    ];
+
+    /// The corrections `GOLDEN_VECTORS` were evaluated with.
+    fn golden_correction(mode: FovCorrection) -> ScreenCorrection {
+        let (aspect_ratio, fov) = match mode {
+            FovCorrection::Panini => (21.0 / 9.0, 110f32),
+            FovCorrection::Stereographic => (16.0 / 9.0, 120.0),
+            _ => (16.0 / 9.0, 90.0),
+        };
+
+        ScreenCorrection {
+            mode,
+            aspect_ratio,
+            cylindricity: 1.25,
+            strength_width_ratio: if mode == FovCorrection::Barrel {
+                0.7
+            } else {
+                0.6
+            },
+            tangent: f32::tan(fov.to_radians() * 0.5),
+            panini_distance: 1.0,
+            panini_compensation: 0.5,
+        }
+    }
+
+    #[test]
+    fn golden_vectors_match_the_shader() {
+        let hlsl = include_str!("../../shaders/ToneMap_PostHook.hlsl").replace('\r', "");
+
+        let start = hlsl.find("// Simple fisheye distortion shader.").unwrap();
+        let end = hlsl.find("// The active FOV correction mapping.").unwrap();
+
+        let fingerprint = hlsl.as_bytes()[start..end]
+            .iter()
+            .fold(0xcbf2_9ce4_8422_2325, |hash: u64, &byte| {
+                (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+            });
+
+        assert_eq!(
+            fingerprint, SHADER_FINGERPRINT,
+            "MapUv* changed in the shader, evaluate GOLDEN_VECTORS again"
+        );
+    }
+
+    #[test]
+    fn map_uv_matches_golden_vectors() {
+        for (mode, vectors) in GOLDEN_VECTORS {
+            let correction = golden_correction(mode);
+
+            for (uv, expected) in vectors {
+                let xy = correction.map_uv(uv);
+
+                assert!(
+                    (xy - expected).abs().max_element() < 1e-5,
+                    "{mode:?}: {uv} -> {xy}, expected {expected}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn unmap_uv_matches_golden_vectors() {
+        for (mode, vectors) in GOLDEN_VECTORS {
+            let correction = golden_correction(mode);
+
+            for (expected, xy) in vectors {
+                let uv = correction.unmap_uv(xy);
+
+                assert!(
+                    (uv - expected).abs().max_element() < 1e-4,
+                    "{mode:?}: {xy} -> {uv}, expected {expected}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn fisheye_round_trip() {
+        corrections(FovCorrection::Fisheye).for_each(assert_round_trip);
+    }
+
+    #[test]
+    fn barrel_round_trip() {
+        corrections(FovCorrection::Barrel).for_each(assert_round_trip);
+    }
+
+    #[test]
+    fn panini_round_trip() {
+        corrections(FovCorrection::Panini).for_each(assert_round_trip);
+    }
+
+    #[test]
+    fn stereographic_round_trip() {
+        corrections(FovCorrection::Stereographic).for_each(assert_round_trip);
+    }
+
+    #[test]
+    fn mappings_keep_the_center() {
+        for mode in [
+            FovCorrection::Fisheye,
+            FovCorrection::Barrel,
+            FovCorrection::Panini,
+            FovCorrection::Stereographic,
+        ] {
+            for correction in corrections(mode) {
+                let center = correction.unmap_uv(Vec2::splat(0.5));
+                assert!((center - 0.5).abs().max_element() < 1e-5, "{correction:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn rendered_image_covers_the_screen() {
+        for mode in [FovCorrection::Panini, FovCorrection::Stereographic] {
+            for correction in corrections(mode) {
+                for corner in [Vec2::ZERO, Vec2::X, Vec2::Y, Vec2::ONE] {
+                    let uv = correction.map_uv(corner);
+                    assert!(
+                        uv.cmpge(Vec2::splat(-PIXEL)).all()
+                            && uv.cmple(Vec2::splat(1.0 + PIXEL)).all(),
+                        "{correction:?}: {corner} -> {uv}"
+                    );
+                }
+            }
+        }
+    }
+}
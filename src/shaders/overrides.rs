@@ -0,0 +1,232 @@
+use std::{
+    fs, io,
+    path::Path,
+    sync::{Mutex, OnceLock},
+};
+
+use crate::config::updater::current_module_path;
+
+/// Game pixel shaders replaced by erfps2, by the name they are registered under.
+pub struct ShaderRegistry<const N: usize>([ShaderOverride; N]);
+
+/// A replacement for the game pixel shader `name`.
+///
+/// A precompiled shader next to erfps2.dll takes priority over the embedded one.
+/// The file is checked against its DXIL container header and loaded once, when the game
+/// registers the shader. Invalid files fall back to the embedded shader.
+///
+/// Overrides are not reloaded while the game is running, as the game keeps using the shader
+/// it created at registration and registering it again is not known to replace it.
+pub struct ShaderOverride {
+    name: &'static str,
+    file_name: &'static str,
+    embedded: &'static [u8],
+    blob: OnceLock<&'static [u8]>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContainerError {
+    TooShort,
+    Magic,
+    Version,
+    Size,
+    Parts,
+    NoDxil,
+}
+
+impl<const N: usize> ShaderRegistry<N> {
+    pub const fn new(overrides: [ShaderOverride; N]) -> Self {
+        Self(overrides)
+    }
+
+    pub fn find(&self, name: &str) -> Option<&ShaderOverride> {
+        self.0.iter().find(|shader| shader.name == name)
+    }
+}
+
+impl ShaderOverride {
+    pub const fn new(name: &'static str, file_name: &'static str, embedded: &'static [u8]) -> Self {
+        Self {
+            name,
+            file_name,
+            embedded,
+            blob: OnceLock::new(),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the blob to register instead of the game's shader, loading the override
+    /// on first use.
+    pub fn blob(&self) -> &'static [u8] {
+        self.blob.get_or_init(|| {
+            let mut path = match current_module_path() {
+                Ok(path) => path,
+                Err(error) => {
+                    log::error!("failed to locate {}: {error}", self.file_name);
+                    return self.embedded;
+                }
+            };
+
+            path.set_file_name(self.file_name);
+
+            if !path.exists() {
+                return self.embedded;
+            }
+
+            match Self::load(&path) {
+                Ok(blob) => {
+                    log::info!("loaded shader override {path:?}");
+
+                    // The shader repository may keep pointers to the blob.
+                    Box::leak(blob)
+                }
+                Err(error) => {
+                    log::error!("failed to load shader override {path:?}: {error}");
+                    self.embedded
+                }
+            }
+        })
+    }
+
+    fn load(path: &Path) -> io::Result<Box<[u8]>> {
+        let blob = fs::read(path)?;
+
+        check_container(&blob)
+            .map_err(|error| io::Error::other(format!("invalid DXIL container ({error:?})")))?;
+
+        Ok(blob.into_boxed_slice())
+    }
+}
+
+/// Checks the container header written by dxc and the bounds of every part.
+pub fn check_container(blob: &[u8]) -> Result<(), ContainerError> {
+    const HEADER_SIZE: usize = 32;
+    const PART_HEADER_SIZE: usize = 8;
+
+    let read_u32 = |offset: usize| {
+        blob.get(offset..offset + 4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()) as usize)
+    };
+
+    if blob.len() < HEADER_SIZE {
+        return Err(ContainerError::TooShort);
+    }
+
+    if &blob[0..4] != b"DXBC" {
+        return Err(ContainerError::Magic);
+    }
+
+    if blob[20..24] != [1, 0, 0, 0] {
+        return Err(ContainerError::Version);
+    }
+
+    if read_u32(24) != Some(blob.len()) {
+        return Err(ContainerError::Size);
+    }
+
+    let part_count = read_u32(28).unwrap();
+    let mut has_dxil = false;
+
+    for i in 0..part_count {
+        let offset = read_u32(HEADER_SIZE + i * 4).ok_or(ContainerError::Parts)?;
+
+        let part_size = read_u32(offset + 4).ok_or(ContainerError::Parts)?;
+        let part_end = (offset + PART_HEADER_SIZE).checked_add(part_size);
+
+        if offset < HEADER_SIZE + part_count * 4 || part_end.is_none_or(|end| end > blob.len()) {
+            return Err(ContainerError::Parts);
+        }
+
+        has_dxil |= &blob[offset..offset + 4] == b"DXIL";
+    }
+
+    if !has_dxil {
+        return Err(ContainerError::NoDxil);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ContainerError, ShaderOverride, ShaderRegistry, check_container};
+
+    fn container(parts: &[(&[u8; 4], &[u8])]) -> Vec<u8> {
+        let header_size = 32 + parts.len() * 4;
+        let parts_size: usize = parts.iter().map(|(_, data)| 8 + data.len()).sum();
+
+        let mut blob = b"DXBC".to_vec();
+        blob.extend([0; 16]);
+        blob.extend(1u32.to_le_bytes());
+        blob.extend(((header_size + parts_size) as u32).to_le_bytes());
+        blob.extend((parts.len() as u32).to_le_bytes());
+
+        let mut offset = header_size;
+
+        for (_, data) in parts {
+            blob.extend((offset as u32).to_le_bytes());
+            offset += 8 + data.len();
+        }
+
+        for (fourcc, data) in parts {
+            blob.extend(*fourcc);
+            blob.extend((data.len() as u32).to_le_bytes());
+            blob.extend(*data);
+        }
+
+        blob
+    }
+
+    #[test]
+    fn finds_overrides_by_name() {
+        let registry = ShaderRegistry::new([
+            ShaderOverride::new("ToneMap_PostOETFPS", "ToneMap_PostHook.ppo", &[]),
+            ShaderOverride::new("Hud_PostHook", "Hud_PostHook.ppo", &[]),
+        ]);
+
+        let found = registry.find("Hud_PostHook").map(ShaderOverride::name);
+        assert_eq!(found, Some("Hud_PostHook"));
+
+        assert!(registry.find("ToneMap_PostOETF").is_none());
+        assert_eq!(registry.0.len(), 2);
+    }
+
+    #[test]
+    fn accepts_dxil_containers() {
+        let blob = container(&[(b"SFI0", &[0; 8]), (b"DXIL", &[1, 2, 3, 4])]);
+        assert_eq!(check_container(&blob), Ok(()));
+    }
+
+    #[test]
+    fn rejects_malformed_containers() {
+        let blob = container(&[(b"DXIL", &[1, 2, 3, 4])]);
+
+        assert_eq!(check_container(&blob[..16]), Err(ContainerError::TooShort));
+        assert_eq!(
+            check_container(&blob[..blob.len() - 1]),
+            Err(ContainerError::Size)
+        );
+
+        let mut magic = blob.clone();
+        magic[0] = b'X';
+        assert_eq!(check_container(&magic), Err(ContainerError::Magic));
+
+        let mut version = blob.clone();
+        version[20] = 2;
+        assert_eq!(check_container(&version), Err(ContainerError::Version));
+
+        let mut part_size = blob.clone();
+        part_size[40] = 0xff;
+        assert_eq!(check_container(&part_size), Err(ContainerError::Parts));
+
+        let mut part_count = blob.clone();
+        part_count[28] = 0xff;
+        assert_eq!(check_container(&part_count), Err(ContainerError::Parts));
+
+        let dxbc = container(&[(b"SHEX", &[0; 4])]);
+        assert_eq!(check_container(&dxbc), Err(ContainerError::NoDxil));
+    }
+}
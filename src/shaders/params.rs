@@ -0,0 +1,421 @@
+use std::{
+    hint, mem,
+    sync::atomic::{AtomicU32, Ordering, fence},
+};
+
+/// The parameter block shared with `ErfpsParams` in "shaders/ErfpsParams.hlsli".
+///
+/// It replaces the tone curve, bloom and color matrix parameters of the `cbToneMap`
+/// constant buffer ([`ShaderParams::REPLACED_FIELDS`]), which "ToneMap_PostHook.hlsl" does
+/// not read. It must follow HLSL constant buffer packing rules: vectors may not straddle
+/// a 16 byte boundary and arrays start on one.
+///
+/// `flags` holds the [`ShaderFlag`] bit fields.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShaderParams {
+    pub version: u32,
+    pub flags: u32,
+    /// Barrel cylindricity and FOV correction strength scaled by the screen width.
+    pub correction: [f32; 2],
+    pub crosshair_scale_reciprocal: [f32; 2],
+    pub crosshair_offset: [f32; 2],
+    pub crosshair_color: u32,
+    pub crosshair_outline_color: u32,
+    pub crosshair_outline_width: f32,
+    pub crosshair_opacity: f32,
+    pub vignette_strength: f32,
+    pub hit_marker_progress: f32,
+    pub hit_marker_color: u32,
+    /// The tangent of half the horizontal FOV.
+    pub fov_tangent: f32,
+    /// Panini projection distance and vertical compensation.
+    pub panini: [f32; 2],
+    /// Contrast adaptive sharpening strength where FOV correction magnifies the image.
+    pub sharpening: f32,
+    /// Scales the game's chromatic aberration offsets.
+    pub chromatic_aberration_scale: f32,
+    pub reserved: [u32; 16],
+    pub crosshair_shape: [u32; 16],
+}
+
+impl ShaderParams {
+    /// Must match `ERFPS_PARAMS_VERSION` in "shaders/ErfpsParams.hlsli".
+    pub const VERSION: u32 = 3;
+
+    /// The space available in the constant buffer.
+    pub const SIZE: usize = 0xd0;
+
+    const WORDS: usize = Self::SIZE / 4;
+
+    /// The offset of the parameter block in `cbToneMap`.
+    pub const CB_OFFSET: usize = 0x10;
+
+    /// The `cbToneMap` fields overwritten by the parameter block, from `CB_OFFSET` up to
+    /// `g_vChromaticAberrationRG` at offset 0xe0, which is left intact.
+    pub const REPLACED_FIELDS: [&str; 11] = [
+        "g_ReinhardParam",
+        "g_ToneMapParam",
+        "g_ToneMapSceneLumScale",
+        "g_AdaptParam",
+        "g_AdaptCenterWeight",
+        "g_BrightPassThreshold",
+        "g_GlareLuminance",
+        "g_BloomBoostColor",
+        "g_vBloomFinalColor",
+        "g_vBloomScaleParam",
+        "g_mtxColorMultiplyer",
+    ];
+
+    pub const DEFAULT: Self = Self {
+        version: Self::VERSION,
+        flags: 0,
+        correction: [0.0; 2],
+        crosshair_scale_reciprocal: [1.0; 2],
+        crosshair_offset: [0.0; 2],
+        crosshair_color: u32::MAX,
+        crosshair_outline_color: u32::MAX,
+        crosshair_outline_width: 0.0,
+        crosshair_opacity: 1.0,
+        vignette_strength: 0.0,
+        hit_marker_progress: 0.0,
+        hit_marker_color: u32::MAX,
+        fov_tangent: 0.0,
+        panini: [0.0; 2],
+        sharpening: 0.0,
+        chromatic_aberration_scale: 1.0,
+        reserved: [0; 16],
+        crosshair_shape: [0; 16],
+    };
+
+    pub fn flag(&self, flag: ShaderFlag) -> bool {
+        self.bits(flag) != 0
+    }
+
+    pub fn set_flag(&mut self, flag: ShaderFlag, state: bool) {
+        self.set_bits(flag, state as u32);
+    }
+
+    pub fn bits(&self, flag: ShaderFlag) -> u32 {
+        (self.flags >> flag.shift) & flag.mask()
+    }
+
+    /// Sets the bits of `flag`, discarding the excess bits of `value`.
+    pub fn set_bits(&mut self, flag: ShaderFlag, value: u32) {
+        let mask = flag.mask();
+        self.flags = self.flags & !(mask << flag.shift) | (value & mask) << flag.shift;
+    }
+
+    const fn to_words(self) -> [u32; Self::WORDS] {
+        // SAFETY: `ShaderParams` has no padding and only 4 byte fields.
+        unsafe { mem::transmute(self) }
+    }
+
+    const fn from_words(words: [u32; Self::WORDS]) -> Self {
+        // SAFETY: as above, any bit pattern is a valid `ShaderParams`.
+        unsafe { mem::transmute(words) }
+    }
+}
+
+/// A bit field of [`ShaderParams::flags`].
+///
+/// Must match the `ERFPS_<name>_SHIFT` and `ERFPS_<name>_WIDTH` definitions
+/// in "shaders/ErfpsParams.hlsli".
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ShaderFlag {
+    pub name: &'static str,
+    pub shift: u32,
+    pub width: u32,
+}
+
+impl ShaderFlag {
+    pub const FOV_CORRECTION: Self = Self::new("FOV_CORRECTION", 0, 1);
+    pub const BARREL: Self = Self::new("BARREL", 1, 1);
+    pub const CROSSHAIR_KIND: Self = Self::new("CROSSHAIR_KIND", 2, 3);
+    pub const VIGNETTE: Self = Self::new("VIGNETTE", 5, 1);
+    pub const CROSSHAIR_BLEND: Self = Self::new("CROSSHAIR_BLEND", 6, 2);
+    /// 0 - none, otherwise the hit marker style plus one.
+    pub const HIT_MARKER_STYLE: Self = Self::new("HIT_MARKER_STYLE", 8, 2);
+    /// 0 - fisheye or barrel, 1 - Panini, 2 - stereographic.
+    pub const PROJECTION: Self = Self::new("PROJECTION", 10, 2);
+    /// Sharpening after FOV correction.
+    pub const SHARPENING: Self = Self::new("SHARPENING", 12, 1);
+    pub const CHROMATIC_ABERRATION_SCALE: Self = Self::new("CHROMATIC_ABERRATION_SCALE", 13, 1);
+
+    pub const ALL: [Self; 9] = [
+        Self::FOV_CORRECTION,
+        Self::BARREL,
+        Self::CROSSHAIR_KIND,
+        Self::VIGNETTE,
+        Self::CROSSHAIR_BLEND,
+        Self::HIT_MARKER_STYLE,
+        Self::PROJECTION,
+        Self::SHARPENING,
+        Self::CHROMATIC_ABERRATION_SCALE,
+    ];
+
+    const fn new(name: &'static str, shift: u32, width: u32) -> Self {
+        Self { name, shift, width }
+    }
+
+    const fn mask(self) -> u32 {
+        (1 << self.width) - 1
+    }
+}
+
+impl Default for ShaderParams {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Publishes [`ShaderParams`] from the game thread to the render thread without locking.
+///
+/// Readers retry while a write is in progress, so writes must not be concurrent.
+pub struct ShaderParamsLock {
+    sequence: AtomicU32,
+    words: [AtomicU32; ShaderParams::WORDS],
+}
+
+impl ShaderParamsLock {
+    pub const fn new(params: ShaderParams) -> Self {
+        Self {
+            sequence: AtomicU32::new(0),
+            // SAFETY: `AtomicU32` has the same layout as `u32`.
+            words: unsafe {
+                mem::transmute::<[u32; ShaderParams::WORDS], [AtomicU32; ShaderParams::WORDS]>(
+                    params.to_words(),
+                )
+            },
+        }
+    }
+
+    pub fn write(&self, params: &ShaderParams) {
+        let sequence = self.sequence.load(Ordering::Relaxed);
+
+        self.sequence
+            .store(sequence.wrapping_add(1), Ordering::Relaxed);
+        fence(Ordering::Release);
+
+        for (word, value) in self.words.iter().zip(params.to_words()) {
+            word.store(value, Ordering::Relaxed);
+        }
+
+        self.sequence
+            .store(sequence.wrapping_add(2), Ordering::Release);
+    }
+
+    pub fn read(&self) -> ShaderParams {
+        loop {
+            let sequence = self.sequence.load(Ordering::Acquire);
+
+            if sequence & 1 != 0 {
+                hint::spin_loop();
+                continue;
+            }
+
+            let words = self
+                .words
+                .each_ref()
+                .map(|word| word.load(Ordering::Relaxed));
+
+            fence(Ordering::Acquire);
+
+            if self.sequence.load(Ordering::Relaxed) == sequence {
+                return ShaderParams::from_words(words);
+            }
+        }
+    }
+}
+
+const _: () = assert!(mem::size_of::<ShaderParams>() == ShaderParams::SIZE);
+
+// Ten `float4` and a `float4x3`, ending at `g_vChromaticAberrationRG`.
+const _: () = assert!(ShaderParams::CB_OFFSET + ShaderParams::SIZE == 0xe0);
+const _: () = assert!(ShaderParams::SIZE == 10 * 0x10 + 0x30);
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        mem::offset_of,
+        sync::{
+            Arc,
+            atomic::{AtomicBool, Ordering},
+        },
+        thread,
+    };
+
+    use super::{ShaderFlag, ShaderParams, ShaderParamsLock};
+
+    #[test]
+    fn field_offsets() {
+        assert_eq!(offset_of!(ShaderParams, version), 0x00);
+        assert_eq!(offset_of!(ShaderParams, flags), 0x04);
+        assert_eq!(offset_of!(ShaderParams, correction), 0x08);
+        assert_eq!(offset_of!(ShaderParams, crosshair_scale_reciprocal), 0x10);
+        assert_eq!(offset_of!(ShaderParams, crosshair_offset), 0x18);
+        assert_eq!(offset_of!(ShaderParams, crosshair_color), 0x20);
+        assert_eq!(offset_of!(ShaderParams, crosshair_outline_color), 0x24);
+        assert_eq!(offset_of!(ShaderParams, crosshair_outline_width), 0x28);
+        assert_eq!(offset_of!(ShaderParams, crosshair_opacity), 0x2c);
+        assert_eq!(offset_of!(ShaderParams, vignette_strength), 0x30);
+        assert_eq!(offset_of!(ShaderParams, hit_marker_progress), 0x34);
+        assert_eq!(offset_of!(ShaderParams, hit_marker_color), 0x38);
+        assert_eq!(offset_of!(ShaderParams, fov_tangent), 0x3c);
+        assert_eq!(offset_of!(ShaderParams, panini), 0x40);
+        assert_eq!(offset_of!(ShaderParams, sharpening), 0x48);
+        assert_eq!(offset_of!(ShaderParams, chromatic_aberration_scale), 0x4c);
+        assert_eq!(offset_of!(ShaderParams, reserved), 0x50);
+        assert_eq!(offset_of!(ShaderParams, crosshair_shape), 0x90);
+    }
+
+    #[test]
+    fn version_matches_hlsl() {
+        let hlsli = include_str!("../../shaders/ErfpsParams.hlsli");
+
+        let version = hlsli
+            .lines()
+            .find_map(|line| line.strip_prefix("#define ERFPS_PARAMS_VERSION "))
+            .and_then(|version| version.trim().parse::<u32>().ok());
+
+        assert_eq!(version, Some(ShaderParams::VERSION));
+    }
+
+    #[test]
+    fn flag_bits() {
+        let mut params = ShaderParams::DEFAULT;
+
+        params.set_flag(ShaderFlag::FOV_CORRECTION, true);
+        params.set_bits(ShaderFlag::CROSSHAIR_KIND, 6);
+        params.set_bits(ShaderFlag::PROJECTION, 2);
+        assert_eq!(params.flags, 0b1000_0001_1001);
+
+        params.set_bits(ShaderFlag::CROSSHAIR_KIND, 0b1011);
+        assert_eq!(params.bits(ShaderFlag::CROSSHAIR_KIND), 0b011);
+        assert!(params.flag(ShaderFlag::FOV_CORRECTION));
+
+        params.set_flag(ShaderFlag::FOV_CORRECTION, false);
+        assert_eq!(params.flags, 0b1000_0000_1100);
+    }
+
+    #[test]
+    fn flags_do_not_overlap() {
+        let mut used = 0u32;
+
+        for flag in ShaderFlag::ALL {
+            let bits = flag.mask() << flag.shift;
+
+            assert_eq!(used & bits, 0, "{} overlaps another flag", flag.name);
+            used |= bits;
+        }
+    }
+
+    #[test]
+    fn flags_match_hlsl() {
+        let hlsli = include_str!("../../shaders/ErfpsParams.hlsli");
+
+        let define = |name: String| {
+            hlsli
+                .lines()
+                .find_map(|line| line.strip_prefix(&format!("#define {name} ")))
+                .and_then(|value| value.trim().parse::<u32>().ok())
+        };
+
+        for flag in ShaderFlag::ALL {
+            let shift = define(format!("ERFPS_{}_SHIFT", flag.name));
+            let width = define(format!("ERFPS_{}_WIDTH", flag.name));
+
+            assert_eq!(shift, Some(flag.shift), "{} shift", flag.name);
+            assert_eq!(width, Some(flag.width), "{} width", flag.name);
+        }
+    }
+
+    #[test]
+    fn replaced_fields_are_unused() {
+        let hlsl = include_str!("../../shaders/ToneMap_PostHook.hlsl");
+
+        let code = hlsl
+            .lines()
+            .map(|line| line.split("//").next().unwrap_or_default())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        for field in ShaderParams::REPLACED_FIELDS {
+            assert!(
+                !code.contains(field),
+                "{field} is overwritten by the params"
+            );
+        }
+
+        // The block sits between the fields it does not overwrite.
+        let cb_tone_map = code
+            .split("cbuffer cbToneMap")
+            .nth(1)
+            .and_then(|cb| cb.split('}').next())
+            .unwrap();
+
+        let fields = cb_tone_map
+            .split(';')
+            .filter_map(|field| field.split_whitespace().last())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            fields[..3],
+            [
+                "g_ToneMapInvSceneLumScale",
+                "g_Erfps",
+                "g_vChromaticAberrationRG"
+            ]
+        );
+    }
+
+    #[test]
+    fn words_round_trip() {
+        let mut params = ShaderParams::DEFAULT;
+        params.crosshair_offset = [0.25, -0.5];
+        params.crosshair_shape[15] = 0xdead_beef;
+
+        let words = params.to_words();
+        assert_eq!(words[0], ShaderParams::VERSION);
+        assert_eq!(words[6], 0.25f32.to_bits());
+        assert_eq!(words[51], 0xdead_beef);
+
+        assert_eq!(ShaderParams::from_words(words), params);
+    }
+
+    #[test]
+    fn reads_are_never_torn() {
+        let lock = Arc::new(ShaderParamsLock::new(ShaderParams::DEFAULT));
+        let done = Arc::new(AtomicBool::new(false));
+
+        let reader = thread::spawn({
+            let lock = lock.clone();
+            let done = done.clone();
+
+            move || {
+                while !done.load(Ordering::Relaxed) {
+                    let params = lock.read();
+                    assert!(
+                        params
+                            .crosshair_shape
+                            .iter()
+                            .all(|&word| word == params.flags)
+                    );
+                }
+            }
+        });
+
+        for i in 0..10_000 {
+            let mut params = ShaderParams::DEFAULT;
+            params.flags = i;
+            params.crosshair_shape = [i; 16];
+
+            lock.write(&params);
+        }
+
+        done.store(true, Ordering::Relaxed);
+        reader.join().unwrap();
+
+        assert_eq!(lock.read().flags, 9_999);
+    }
+}
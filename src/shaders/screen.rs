@@ -1,79 +1,305 @@
-use glam::Vec2;
+use fromsoftware_shared::F32ViewMatrix;
+use glam::{Vec2, Vec3, Vec4};
 
-use crate::shaders::{get_fov_correction, get_shader_flag};
+use crate::{
+    config::FovCorrection,
+    shaders::{get_aspect_ratio, params::ShaderFlag, shader_params},
+};
 
-pub fn correct_screen_coords(xy: Vec2) -> Vec2 {
-    if !get_shader_flag(0) {
-        return xy;
+/// Projects a world space point to normalized screen coordinates as they appear on screen,
+/// with FOV correction applied. Returns `None` for points outside of the view.
+pub fn world_to_screen(camera: F32ViewMatrix, fov: f32, point: Vec3) -> Option<Vec2> {
+    let right = Vec4::from(camera.0).truncate();
+    let up = Vec4::from(camera.1).truncate();
+    let forward = Vec4::from(camera.2).truncate();
+    let origin = Vec4::from(camera.3).truncate();
+
+    let to_point = point - origin;
+    let view = Vec3::new(to_point.dot(right), to_point.dot(up), to_point.dot(forward));
+
+    view_to_screen(view, fov, get_aspect_ratio()).map(correct_screen_coords)
+}
+
+/// Projects a view space point to normalized screen coordinates without FOV correction.
+///
+/// `fov` is the horizontal FOV, like `pers_cam.fov` and `Config::fov`.
+fn view_to_screen(view: Vec3, fov: f32, aspect_ratio: f32) -> Option<Vec2> {
+    if view.z <= 0.0 {
+        return None;
     }
 
-    let xy = xy.clamp(Vec2::ZERO, Vec2::ONE);
-    if get_shader_flag(1) {
-        correct_screen_coords_barrel(xy)
-    } else {
-        correct_screen_coords_fisheye(xy)
+    let half_width = (fov * 0.5).tan();
+    let half_height = half_width / aspect_ratio;
+
+    let x = view.x / (view.z * half_width);
+    let y = view.y / (view.z * half_height);
+
+    if x.abs() > 1.0 || y.abs() > 1.0 {
+        return None;
     }
+
+    Some(Vec2::new(x * 0.5 + 0.5, 0.5 - y * 0.5))
+}
+
+/// Scales normalized screen coordinates so that distances are equal along both axes.
+pub fn to_square_screen(xy: Vec2) -> Vec2 {
+    Vec2::new(xy.x * get_aspect_ratio(), xy.y)
 }
 
-fn correct_screen_coords_fisheye(uv: Vec2) -> Vec2 {
-    let uv = uv - 0.5;
+/// Moves normalized screen coordinates to where they appear on screen with FOV correction.
+pub fn correct_screen_coords(xy: Vec2) -> Vec2 {
+    ScreenCorrection::current().map_or(xy, |correction| correction.apply(xy))
+}
+
+/// The layout of FE (HUD) coordinates on screen.
+///
+/// FE coordinates span 1920x1080 over the largest area of `hud_aspect_ratio` centered
+/// on the screen. The game lays out its HUD in 16:9, letterboxed on 16:10 and pillarboxed
+/// on ultrawide displays. Neither `CSFeManImp` nor the mapped rendering structures expose
+/// the HUD layout, so this is an assumption configured by `fov.hud_aspect_ratio` rather
+/// than read from the game.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HudLayout {
+    pub aspect_ratio: f32,
+    pub hud_aspect_ratio: f32,
+}
 
-    let strength = get_fov_correction().1;
-    let k_max = 1.0 + strength * 0.125;
+impl HudLayout {
+    pub const FE_SIZE: Vec2 = Vec2::new(1920.0, 1080.0);
 
-    let uvk = uv * k_max;
+    /// The layout for the current aspect ratio, with the HUD in an area of `hud_aspect_ratio`
+    /// or the whole screen for `None`.
+    pub fn current(hud_aspect_ratio: Option<f32>) -> Self {
+        let aspect_ratio = get_aspect_ratio();
 
-    if uvk.x == 0.0 {
-        return uvk + 0.5;
+        Self {
+            aspect_ratio,
+            hud_aspect_ratio: hud_aspect_ratio.unwrap_or(aspect_ratio),
+        }
     }
 
-    let x = uvk.x.abs();
-    let mut y = x + 0.07 * (strength * x * x) - 0.5 * (strength * x * x) * x;
+    /// The size of the HUD area in normalized screen coordinates.
+    pub fn safe_area(&self) -> Vec2 {
+        let ratio = self.hud_aspect_ratio / self.aspect_ratio;
+
+        if ratio < 1.0 {
+            Vec2::new(ratio, 1.0)
+        } else {
+            Vec2::new(1.0, ratio.recip())
+        }
+    }
+
+    pub fn fe_to_screen(&self, fe: Vec2) -> Vec2 {
+        (fe / Self::FE_SIZE - 0.5) * self.safe_area() + 0.5
+    }
+
+    pub fn screen_to_fe(&self, xy: Vec2) -> Vec2 {
+        ((xy - 0.5) / self.safe_area() + 0.5) * Self::FE_SIZE
+    }
+
+    /// Moves FE coordinates of a world-anchored HUD element to where its anchor
+    /// appears on screen with FOV correction.
+    pub fn correct(&self, correction: &ScreenCorrection, fe: Vec2) -> Vec2 {
+        self.screen_to_fe(correction.apply(self.fe_to_screen(fe)))
+    }
+}
+
+/// The FOV correction parameters shared with "shaders/ToneMap_PostHook.hlsl".
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScreenCorrection {
+    pub mode: FovCorrection,
+    pub aspect_ratio: f32,
+    pub cylindricity: f32,
+    pub strength_width_ratio: f32,
+    pub tangent: f32,
+    pub panini_distance: f32,
+    pub panini_compensation: f32,
+}
+
+impl ScreenCorrection {
+    /// Returns the active FOV correction, or `None` if it is disabled.
+    pub fn current() -> Option<Self> {
+        let params = shader_params();
+
+        if !params.flag(ShaderFlag::FOV_CORRECTION) {
+            return None;
+        }
 
-    let f = |y: f32| (strength * (y * y)) * (y * y) + y - x;
-    let f_prime = |y: f32| 4.0 * (strength * (y * y)) * y + 1.0;
+        let mode = match (
+            params.flag(ShaderFlag::BARREL),
+            params.bits(ShaderFlag::PROJECTION),
+        ) {
+            (true, _) => FovCorrection::Barrel,
+            (false, 1) => FovCorrection::Panini,
+            (false, 2) => FovCorrection::Stereographic,
+            (false, _) => FovCorrection::Fisheye,
+        };
 
-    y -= f(y) / f_prime(y);
+        let [cylindricity, strength_width_ratio] = params.correction;
+        let [panini_distance, panini_compensation] = params.panini;
 
-    let uv = Vec2::new(y.copysign(uvk.x), uvk.y * y / x);
+        Some(Self {
+            mode,
+            aspect_ratio: get_aspect_ratio(),
+            cylindricity,
+            strength_width_ratio,
+            tangent: params.fov_tangent,
+            panini_distance,
+            panini_compensation,
+        })
+    }
+
+    /// Maps normalized screen coordinates of the rendered image to the corrected image,
+    /// see [`ScreenCorrection::unmap_uv`].
+    pub fn apply(&self, xy: Vec2) -> Vec2 {
+        let xy = xy.clamp(Vec2::ZERO, Vec2::ONE);
 
-    uv + 0.5
+        self.unmap_uv(xy)
+    }
 }
 
-// Source: https://www.decarpentier.nl/lens-distortion
-//
-// Copyright (c) 2015, Giliam de Carpentier
-// All rights reserved.
-//
-// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the following conditions are met:
-//
-// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following disclaimer.
-//
-// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the following disclaimer in the
-// documentation and/or other materials provided with the distribution.
-//
-// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED
-// TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR
-// CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
-// PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
-// LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
-// SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
-fn correct_screen_coords_barrel(xy: Vec2) -> Vec2 {
-    const ASPECT_RATIO: f32 = 16.0 / 9.0;
-
-    let (cylindrical_ratio, strength_width_ratio) = get_fov_correction();
-    let scaled_height = strength_width_ratio / ASPECT_RATIO;
-    let cyl_aspect_ratio = ASPECT_RATIO * cylindrical_ratio;
-    let cyl_aspect_ratio_sq = cyl_aspect_ratio * cyl_aspect_ratio;
-    let aspect_diag_sq = ASPECT_RATIO * ASPECT_RATIO + 1.0;
-    let diag_sq = scaled_height * scaled_height * aspect_diag_sq;
-    let signed_uv = 2.0 * xy - 1.0;
-
-    let z = 0.5 * (diag_sq + 1.0).sqrt() + 0.5;
-    let ny = (z - 1.0) / (cyl_aspect_ratio_sq + 1.0);
-    let nx = cyl_aspect_ratio_sq * ny;
-    let p_sq = signed_uv * signed_uv;
-    let ivp = (0.25 + z * (nx * p_sq.x + ny * p_sq.y)).sqrt();
-
-    z * signed_uv / (0.5 + ivp) * 0.5 + 0.5
+#[cfg(test)]
+mod tests {
+    use glam::{Vec2, Vec3};
+
+    use super::{HudLayout, ScreenCorrection, view_to_screen};
+    use crate::config::FovCorrection;
+
+    const ASPECT_RATIOS: [f32; 4] = [16.0 / 10.0, 16.0 / 9.0, 21.0 / 9.0, 32.0 / 9.0];
+
+    #[test]
+    fn hud_layout_fits_16_by_9() {
+        let assert_near = |a: Vec2, b: Vec2| assert!((a - b).abs().max_element() < 1e-5, "{a} {b}");
+
+        let layout = HudLayout {
+            aspect_ratio: 16.0 / 9.0,
+            hud_aspect_ratio: 16.0 / 9.0,
+        };
+        assert_near(layout.safe_area(), Vec2::ONE);
+
+        let ultrawide = HudLayout {
+            aspect_ratio: 32.0 / 9.0,
+            hud_aspect_ratio: 16.0 / 9.0,
+        };
+        assert_near(ultrawide.fe_to_screen(Vec2::ZERO), Vec2::new(0.25, 0.0));
+        assert_near(
+            ultrawide.fe_to_screen(Vec2::new(960.0, 540.0)),
+            Vec2::splat(0.5),
+        );
+
+        let letterboxed = HudLayout {
+            aspect_ratio: 16.0 / 10.0,
+            hud_aspect_ratio: 16.0 / 9.0,
+        };
+        assert_near(
+            letterboxed.fe_to_screen(HudLayout::FE_SIZE),
+            Vec2::new(1.0, 0.95),
+        );
+    }
+
+    #[test]
+    fn hud_layout_fills_the_screen() {
+        for aspect_ratio in ASPECT_RATIOS {
+            let layout = HudLayout {
+                aspect_ratio,
+                hud_aspect_ratio: aspect_ratio,
+            };
+
+            assert_eq!(layout.safe_area(), Vec2::ONE);
+            assert_eq!(layout.fe_to_screen(HudLayout::FE_SIZE), Vec2::ONE);
+        }
+    }
+
+    #[test]
+    fn fov_is_horizontal() {
+        for aspect_ratio in ASPECT_RATIOS {
+            for fov in [60f32, 90.0, 130.0].map(f32::to_radians) {
+                let half_width = (fov * 0.5).tan();
+                let half_height = half_width / aspect_ratio;
+
+                let right_edge = view_to_screen(Vec3::new(half_width, 0.0, 1.0), fov, aspect_ratio);
+                assert_eq!(right_edge, Some(Vec2::new(1.0, 0.5)));
+
+                let top_edge = view_to_screen(Vec3::new(0.0, half_height, 1.0), fov, aspect_ratio);
+                assert_eq!(top_edge, Some(Vec2::new(0.5, 0.0)));
+
+                let outside = Vec3::new(half_width * 1.01, 0.0, 1.0);
+                assert_eq!(view_to_screen(outside, fov, aspect_ratio), None);
+
+                let behind = Vec3::new(0.0, 0.0, -1.0);
+                assert_eq!(view_to_screen(behind, fov, aspect_ratio), None);
+            }
+        }
+    }
+
+    #[test]
+    fn hud_layout_round_trip() {
+        for aspect_ratio in ASPECT_RATIOS {
+            let layout = HudLayout {
+                aspect_ratio,
+                hud_aspect_ratio: 16.0 / 9.0,
+            };
+            let fe = Vec2::new(300.0, 900.0);
+
+            let error = layout.screen_to_fe(layout.fe_to_screen(fe)) - fe;
+            assert!(error.abs().max_element() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn hud_correction_matches_the_shader() {
+        let layout = HudLayout {
+            aspect_ratio: 21.0 / 9.0,
+            hud_aspect_ratio: 16.0 / 9.0,
+        };
+
+        // FE coordinates whose screen position `MapUv*` in "shaders/ToneMap_PostHook.hlsl"
+        // maps to the uncorrected position, solved from the shader expressions.
+        let cases = [
+            (
+                ScreenCorrection {
+                    mode: FovCorrection::Barrel,
+                    aspect_ratio: layout.aspect_ratio,
+                    cylindricity: 1.25,
+                    strength_width_ratio: 0.7,
+                    tangent: 1.0,
+                    panini_distance: 0.0,
+                    panini_compensation: 0.0,
+                },
+                [
+                    (Vec2::new(200.0, 150.0), Vec2::new(145.061, 121.8076)),
+                    (Vec2::new(1700.0, 900.0), Vec2::new(1755.9848, 927.2358)),
+                    (Vec2::new(960.0, 300.0), Vec2::new(960.0, 269.9734)),
+                ],
+            ),
+            (
+                ScreenCorrection {
+                    mode: FovCorrection::Panini,
+                    aspect_ratio: layout.aspect_ratio,
+                    cylindricity: 0.0,
+                    strength_width_ratio: 0.0,
+                    tangent: f32::tan(55f32.to_radians()),
+                    panini_distance: 1.0,
+                    panini_compensation: 0.5,
+                },
+                [
+                    (Vec2::new(200.0, 150.0), Vec2::new(61.2331, 44.6402)),
+                    (Vec2::new(1700.0, 900.0), Vec2::new(1840.6889, 998.8146)),
+                    (Vec2::new(960.0, 300.0), Vec2::new(960.0, 210.7864)),
+                ],
+            ),
+        ];
+
+        for (correction, vectors) in cases {
+            for (fe, expected) in vectors {
+                let corrected = layout.correct(&correction, fe);
+
+                assert!(
+                    (corrected - expected).abs().max_element() < 0.05,
+                    "{:?}: {fe} -> {corrected}, expected {expected}",
+                    correction.mode
+                );
+            }
+        }
+    }
 }